@@ -86,6 +86,8 @@ export default function ProposalWizard() {
     const result = await submitProposal({
       title: data.title,
       description: data.description,
+      category: data.category,
+      evidenceRequirements: data.evidenceRequirements,
       bondAmount: data.bondAmount,
       endTimestamp: Math.floor(data.endDate.getTime() / 1000), // Convert to Unix timestamp
     });
@@ -14,14 +14,46 @@ import toast from 'react-hot-toast';
 
 // Import ProposalSystem IDL
 import ProposalSystemIDL from '../solana/idl/proposal_system.json';
+import { MARKET_CATEGORIES } from '../types/proposal';
 
 export interface ProposalSubmitData {
   title: string;
   description: string;
+  category: string;
+  evidenceRequirements?: string; // Resolution source links/requirements
   bondAmount: number; // in ZMart (will convert to lamports)
   endTimestamp: number; // Unix timestamp
 }
 
+const MAX_RESOLUTION_SOURCE_LEN = 200;
+
+/**
+ * Build the on-chain metadata argument: category, resolution source and the
+ * SHA-256 of the off-chain metadata JSON stored alongside the proposal.
+ */
+async function buildMetadataArgs(data: ProposalSubmitData) {
+  const category = (MARKET_CATEGORIES as readonly string[]).includes(data.category)
+    ? data.category.toLowerCase()
+    : 'other';
+  const resolutionSource =
+    data.evidenceRequirements?.match(/https?:\/\/\S+/)?.[0].slice(0, MAX_RESOLUTION_SOURCE_LEN) ?? '';
+
+  const metadataJson = JSON.stringify({
+    title: data.title,
+    description: data.description,
+    category: data.category,
+    evidenceRequirements: data.evidenceRequirements ?? '',
+  });
+  const hash = await crypto.subtle.digest('SHA-256', new TextEncoder().encode(metadataJson));
+
+  return {
+    category: { [category]: {} },
+    tags: [] as string[],
+    resolutionSource,
+    metadataHash: Array.from(new Uint8Array(hash)),
+  };
+}
+
 export interface ProposalSubmitResult {
   success: boolean;
   signature?: string;
@@ -119,6 +151,12 @@ export function useProposalSubmit() {
       );
       console.log('🔑 Proposal PDA:', proposalPDA.toBase58());
 
+      const [proposalMetadataPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from('proposal-metadata'), proposalId.toArrayLike(Buffer, 'le', 8)],
+        programId
+      );
+      const metadata = await buildMetadataArgs(data);
+
       const parameterStorageProgram = new PublicKey(
         process.env.NEXT_PUBLIC_PARAMETER_STORAGE_ID || 'J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD'
       );
@@ -140,10 +178,12 @@ export function useProposalSubmit() {
           data.title,
           data.description,
           bondTier,
-          new BN(data.endTimestamp)
+          new BN(data.endTimestamp),
+          metadata
         )
         .accounts({
           proposal: proposalPDA,
+          proposalMetadata: proposalMetadataPDA,
           globalParameters,
           creator: wallet.publicKey,
           systemProgram: SystemProgram.programId,
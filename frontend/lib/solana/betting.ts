@@ -29,10 +29,18 @@ function getBondManagerProgramId(): PublicKey {
   )
 }
 
+function getReferrer(): PublicKey | null {
+  const referrer = process.env.NEXT_PUBLIC_REFERRER_WALLET
+  return referrer ? new PublicKey(referrer) : null
+}
+
 export interface PlaceBetParams {
   marketId: number
   amount: number // in SOL
   outcome: 'YES' | 'NO'
+  referrer?: PublicKey // Integration credited with a share of the platform fee
+  minYesOddsBps?: number // Reject the bet if YES odds land below this (default 0)
+  maxYesOddsBps?: number // Reject the bet if YES odds land above this (default 10000)
   publicKey: PublicKey
   connection: Connection
   signTransaction: (tx: Transaction) => Promise<Transaction>
@@ -92,16 +100,24 @@ export async function placeBet(params: PlaceBetParams): Promise<PlaceBetResult>
       getCoreMarketsProgramId()
     )
 
-    // Get market account to read total_bets for user bet PDA
-    const marketAccount: any = await (program.account as any).market.fetch(marketPda)
-    const totalBets = marketAccount.totalBets as number
+    // User bets are indexed by the bettor's position bet_count (0 before the first bet)
+    const [userPositionPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user-position'),
+        marketPda.toBuffer(),
+        publicKey.toBuffer()
+      ],
+      getCoreMarketsProgramId()
+    )
+    const positionAccount: any = await (program.account as any).userPosition.fetchNullable(userPositionPda)
+    const betIndex = positionAccount ? new BN(positionAccount.betCount) : new BN(0)
 
     const [userBetPda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from('user-bet'),
         marketPda.toBuffer(),
         publicKey.toBuffer(),
-        new BN(totalBets).toArrayLike(Buffer, 'le', 8)
+        betIndex.toArrayLike(Buffer, 'le', 8)
       ],
       getCoreMarketsProgramId()
     )
@@ -111,6 +127,11 @@ export async function placeBet(params: PlaceBetParams): Promise<PlaceBetResult>
       getParameterStorageProgramId()
     )
 
+    const [globalTogglesPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('global-toggles')],
+      getParameterStorageProgramId()
+    )
+
     const [bondEscrowPda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from('bond-escrow'),
@@ -119,6 +140,25 @@ export async function placeBet(params: PlaceBetParams): Promise<PlaceBetResult>
       getBondManagerProgramId()
     )
 
+    // Referral: credit the integration (never the bettor themselves)
+    const referrer = params.referrer ?? getReferrer()
+    const referralAccounts: Record<string, PublicKey | null> = {
+      referrer: null,
+      referralFees: null
+    }
+    if (referrer && !referrer.equals(publicKey)) {
+      const [referralFeesPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('referral-fees'),
+          marketPda.toBuffer(),
+          referrer.toBuffer()
+        ],
+        getCoreMarketsProgramId()
+      )
+      referralAccounts.referrer = referrer
+      referralAccounts.referralFees = referralFeesPda
+    }
+
     // Convert SOL to lamports
     const amountLamports = new BN(amount * LAMPORTS_PER_SOL)
 
@@ -127,16 +167,19 @@ export async function placeBet(params: PlaceBetParams): Promise<PlaceBetResult>
 
     // Build transaction
     const tx = await (program as any).methods
-      .placeBet(betSide, amountLamports)
+      .placeBet(betSide, amountLamports, params.minYesOddsBps ?? 0, params.maxYesOddsBps ?? 10000)
       .accounts({
         market: marketPda,
+        userPosition: userPositionPda,
         userBet: userBetPda,
         globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
         bondEscrow: bondEscrowPda,
         bettor: publicKey,
         systemProgram: SystemProgram.programId,
         parameterStorageProgram: getParameterStorageProgramId(),
-        bondManagerProgram: getBondManagerProgramId()
+        bondManagerProgram: getBondManagerProgramId(),
+        ...referralAccounts
       })
       .transaction()
 
@@ -16,6 +16,7 @@ const PARAMETER_STORAGE_PROGRAM_ID_STR: &str = "J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8
 /// - Bond tiers from ParameterStorage (tier_1/2/3)
 /// - Refund logic: 100% on success, 50% on rejection, 0% on slash
 /// - Creator fee accumulation and withdrawal
+/// - Processed escrows close: retained bond to the platform, rent to the creator
 ///
 /// Security:
 /// - PDA-based escrow prevents unauthorized access
@@ -260,6 +261,63 @@ pub mod bond_manager {
 
         Ok(())
     }
+
+    /// Close a processed bond escrow
+    ///
+    /// Requires the bond to be refunded (fully, partially or slashed) and creator
+    /// fees claimed. The retained part of the bond goes to the platform wallet;
+    /// rent returns to the creator.
+    pub fn close_bond_escrow(ctx: Context<CloseBondEscrow>) -> Result<()> {
+        let expected_program_id = Pubkey::from_str(PARAMETER_STORAGE_PROGRAM_ID_STR)
+            .map_err(|_| BondError::InvalidParameterStorageProgram)?;
+        require!(
+            ctx.accounts.parameter_storage_program.key() == expected_program_id,
+            BondError::InvalidParameterStorageProgram
+        );
+
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        require!(
+            ctx.accounts.platform_wallet.key() == params.authority,
+            BondError::Unauthorized
+        );
+
+        let escrow = &ctx.accounts.bond_escrow;
+
+        require!(
+            escrow.status != BondStatus::Active,
+            BondError::BondStillActive
+        );
+        require!(
+            escrow.accumulated_fees == 0,
+            BondError::UnclaimedCreatorFees
+        );
+
+        // Everything above rent exemption is bond kept by the protocol
+        let escrow_info = escrow.to_account_info();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(escrow_info.data_len());
+        let retained_bond = escrow_info.lamports().saturating_sub(rent_exempt_minimum);
+        if retained_bond > 0 {
+            **escrow_info.try_borrow_mut_lamports()? -= retained_bond;
+            **ctx.accounts.platform_wallet.try_borrow_mut_lamports()? += retained_bond;
+        }
+
+        emit!(BondEscrowClosedEvent {
+            market_id: escrow.market_id,
+            creator: escrow.creator,
+            status: escrow.status.clone(),
+            retained_bond,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Bond escrow closed for market {}: {} SOL retained",
+            escrow.market_id,
+            retained_bond as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
 }
 
 // ==============================================================================
@@ -392,6 +450,37 @@ pub struct AddCreatorFees<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CloseBondEscrow<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"bond-escrow", bond_escrow.market_id.to_le_bytes().as_ref()],
+        bump = bond_escrow.bump
+    )]
+    pub bond_escrow: Account<'info, BondEscrow>,
+
+    /// CHECK: Global parameters from ParameterStorage - validated via seeds,
+    /// deserialized in the handler (owned by ParameterStorage, not this program)
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Bond creator who paid the rent
+    #[account(mut, address = bond_escrow.creator @ BondError::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// CHECK: Platform wallet - validated against GlobalParameters.authority
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
 // ==============================================================================
 // External Account Structures (from ParameterStorage)
 // ==============================================================================
@@ -452,6 +541,15 @@ pub struct CreatorFeesClaimedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BondEscrowClosedEvent {
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub status: BondStatus,
+    pub retained_bond: u64, // Sent to the platform wallet
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct CreatorFeesAddedEvent {
     pub market_id: u64,
@@ -483,4 +581,10 @@ pub enum BondError {
 
     #[msg("Invalid parameter storage program: must be J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD")]
     InvalidParameterStorageProgram,
+
+    #[msg("Bond is still active: refund or slash it before closing")]
+    BondStillActive,
+
+    #[msg("Creator fees must be claimed before closing the escrow")]
+    UnclaimedCreatorFees,
 }
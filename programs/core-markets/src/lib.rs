@@ -1,15 +1,83 @@
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+use std::str::FromStr;
 
 declare_id!("6BBZWsJZq23k2NX3YnENgXTEPhbVEHXYmPxmamN83eEV");
 
+/// Market Resolution Program ID string (for resolver PDA validation)
+const MARKET_RESOLUTION_PROGRAM_ID_STR: &str = "Hcxxt6W1HmKQmnUvqpgzNEqVG611Yzt2i4DUvwvkLRf2";
+
+/// Parameter Storage Program ID string (owner of the feature toggles PDA)
+const PARAMETER_STORAGE_PROGRAM_ID_STR: &str = "J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD";
+
+/// Seed of the MarketResolution PDA that signs resolve/cancel CPIs
+pub const RESOLVER_AUTHORITY_SEED: &[u8] = b"resolver-authority";
+
 /// BMAD-Zmart Core Markets
 ///
 /// Central betting mechanics with fee distribution and odds calculation.
 ///
 /// Architecture:
 /// - Market accounts track liquidity pools (yes_pool, no_pool)
-/// - UserBet accounts track individual positions
-/// - Fees distributed: platform, creator, treasury
+/// - Categorical markets track one pool per outcome (2-16 outcomes)
+/// - Scalar markets track long/short pools and pay out linearly between bounds
+/// - Collateral is native SOL or an SPL/Token-2022 mint held in a market vault
+/// - Betting and position sales close at betting_closes_at, a
+///   GlobalParameters.betting_cutoff_seconds buffer before end_date
+/// - Positions can be sold before betting closes at their current value plus
+///   the sold portion's entry fees, minus an exit fee
+/// - A wallet's cumulative stake per market is capped (per-market override, else
+///   GlobalParameters / CollateralConfig max_position_per_wallet)
+/// - seed_liquidity lets the creator open a binary parimutuel market at a chosen
+///   YES probability: a fee-free YES/NO bet pair in the creator's own position
+/// - If the event was decided early, bets placed at or after the reported
+///   decision time are voided (stake and fees refunded on claim) before resolution
+/// - Early resolution: a proposal halts betting at once and, unless a position
+///   holder challenges it, settles the market after a short challenge period;
+///   a challenge sends the market to the normal MarketResolution vote, with the
+///   challenger's bond held in ["early-challenge", market] and forfeited to the
+///   treasury if the vote agrees with the proposal
+/// - Optional LMSR pricing: shares bought at the cost-function price, each
+///   winning share redeems for exactly 1 unit, creator subsidy caps the loss
+/// - UserBet accounts track individual bets: ["user-bet", market, bettor, bet_index]
+/// - UserPosition aggregates a bettor's bets per market: ["user-position", market, bettor]
+/// - ParameterStorage feature toggles gate creation, betting and resolution;
+///   claims and refunds stay open during an emergency pause
+/// - Fees distributed on resolution: platform, creator, treasury
+/// - Treasury PDA ["treasury"] accrues treasury_fee_bps of every bet;
+///   withdrawals are authority-only and emit an audit event
+/// - Optional referrer on place_bet earns a share of the platform fee, accrued in
+///   ["referral-fees", market, referrer] and claimable once the market resolves
+/// - Finished bets, positions and referral accounts are closed permissionlessly
+///   (rent to the original payer); close_market then sweeps dust to the treasury
+///   once GlobalParameters.claim_period_seconds has passed since settlement
+/// - Gasless betting: place_bet's fee_payer (a relayer, or the bettor) funds
+///   account rent, recorded as `payer` and refunded on close; stake and payouts
+///   always move between the market and the bettor
+/// - MarketMetadata ["market-metadata", market] holds category, tags, resolution
+///   source and the hash of the off-chain metadata JSON, keeping Market small
+/// - Conditional markets name a parent market and the outcome they assume; they
+///   take bets only while that outcome is still possible, resolve only once the
+///   parent resolved that way, and are cancelled (full refunds) otherwise
+/// - Private markets: an optional allowlist_root (Merkle root of member pubkeys)
+///   makes place_bet require a proof of the bettor; see verify_merkle_proof
+/// - Parlays: one ticket ["parlay", pool, bettor, nonce] on 2-4 binary legs, staked
+///   in a ParlayPool ["parlay-pool", pool_id] shared by tickets on the same legs
+///   and paid only if every leg wins; cancelled legs drop out and reprice the pool.
+///   Fees stay in the pool until it closes and are refunded with the stakes when
+///   every leg cancels or nobody wins
+/// - Markets count their unsettled parlay pools and conditional children
+///   (open_dependents); close_market waits until settle_parlay_pool /
+///   settle_condition release them
+/// - MarketSeries ["market-series", series_id] templates recurring markets; the
+///   permissionless spawn_next_market crank creates each next market (binary,
+///   deterministic market_id) once the previous one has ended, and settling a
+///   child market adds it to the series stats
 /// - Real-time odds: yes% = yes_pool / (yes_pool + no_pool)
 #[program]
 pub mod core_markets {
@@ -18,23 +86,45 @@ pub mod core_markets {
     /// Create a new prediction market
     ///
     /// Admin-only for Epic 1. Epic 2 adds proposal governance.
+    /// Binary and scalar markets pass no outcome labels; categorical markets pass 2-16.
+    /// LMSR pricing (binary only) requires the creator to fund b * ln(2) up front.
+    /// `metadata` is stored in the market's MarketMetadata PDA.
+    /// Conditional markets pass the active `parent_market` account and the
+    /// parent outcome they assume as `required_outcome` (binary/categorical parents).
+    /// Private markets pass the Merkle root of their members as `allowlist_root`.
     pub fn create_market(
         ctx: Context<CreateMarket>,
         market_id: u64,
         title: String,
         description: String,
         end_date: i64,
+        market_kind: MarketKind,
+        outcome_labels: Vec<String>,
+        pricing_mode: PricingMode,
+        metadata: MarketMetadataArgs,
+        required_outcome: Option<BetSide>,
+        allowlist_root: Option<[u8; 32]>,
     ) -> Result<()> {
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.market_creation_enabled,
+            MarketError::MarketCreationDisabled,
+        )?;
+
+        // Deserialize global_parameters from parameter_storage program
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
         let market = &mut ctx.accounts.market;
         let clock = Clock::get()?;
 
         // Validate inputs
         require!(
-            !title.is_empty() && title.len() <= 128,
+            !title.is_empty() && title.len() <= MAX_TITLE_LEN,
             MarketError::InvalidTitle
         );
         require!(
-            !description.is_empty() && description.len() <= 512,
+            !description.is_empty() && description.len() <= MAX_DESCRIPTION_LEN,
             MarketError::InvalidDescription
         );
         require!(
@@ -42,34 +132,115 @@ pub mod core_markets {
             MarketError::InvalidEndDate
         );
 
-        // Initialize market
-        market.market_id = market_id;
-        market.creator = ctx.accounts.creator.key();
-        market.title = title.clone();
-        market.description = description;
-        market.end_date = end_date;
+        require!(
+            market_id & SERIES_MARKET_ID_FLAG == 0,
+            MarketError::ReservedMarketId
+        );
+
+        // Duration window from GlobalParameters
+        validate_market_duration(end_date - clock.unix_timestamp, &params)?;
+
+        validate_market_kind(&market_kind, &outcome_labels)?;
+        validate_pricing_mode(&market_kind, &pricing_mode)?;
+        metadata.validate()?;
+
+        // Conditional market: parent must still be able to resolve to required_outcome
+        let parent_market = match (&ctx.accounts.parent_market, &required_outcome) {
+            (None, None) => None,
+            (Some(parent), Some(outcome)) => {
+                require!(
+                    parent.status == MarketStatus::Active && !parent.is_scalar(),
+                    MarketError::InvalidCondition
+                );
+                parent.validate_bet_side(outcome)?;
+                Some(parent.key())
+            }
+            _ => return err!(MarketError::InvalidCondition),
+        };
 
-        // Initial pools (empty)
-        market.yes_pool = 0;
-        market.no_pool = 0;
-        market.total_volume = 0;
+        // SPL collateral: mint must be registered and enabled, vault created above
+        let (collateral_mint, collateral_vault) = match &ctx.accounts.collateral_mint {
+            Some(mint) => {
+                let config = ctx
+                    .accounts
+                    .collateral_config
+                    .as_ref()
+                    .ok_or(MarketError::MissingCollateralAccounts)?;
+                require!(config.mint == mint.key(), MarketError::InvalidCollateralAccount);
+                require!(config.enabled, MarketError::CollateralDisabled);
+                let vault = ctx
+                    .accounts
+                    .market_vault
+                    .as_ref()
+                    .ok_or(MarketError::MissingCollateralAccounts)?;
+                (Some(mint.key()), Some(vault.key()))
+            }
+            None => (None, None),
+        };
 
-        // Fee tracking (initially zero)
-        market.total_platform_fees = 0;
-        market.total_creator_fees = 0;
+        init_market(
+            market,
+            market_id,
+            ctx.accounts.creator.key(),
+            title.clone(),
+            description,
+            end_date,
+            market_kind.clone(),
+            outcome_labels,
+            pricing_mode.clone(),
+            &params,
+            &clock,
+            ctx.bumps.market,
+        );
 
-        // Payout tracking (initially zero)
-        market.total_claimed = 0;
+        // Collateral (None = native SOL)
+        market.collateral_mint = collateral_mint;
+        market.collateral_vault = collateral_vault;
+
+        // Condition on the parent market (None = unconditional); the parent
+        // cannot be closed until settle_condition releases it
+        market.parent_market = parent_market;
+        market.required_outcome = required_outcome.clone();
+        if let Some(parent) = ctx.accounts.parent_market.as_mut() {
+            parent.open_dependents = parent.open_dependents
+                .checked_add(1)
+                .ok_or(MarketError::TotalBetsOverflow)?;
+        }
 
-        // Status
-        market.status = MarketStatus::Active;
-        market.created_at = clock.unix_timestamp;
-        market.resolved_outcome = None;
+        // Allowlist (None = anyone can bet)
+        market.allowlist_root = allowlist_root;
+
+        let outcome_count = market.outcome_labels.len() as u8;
+
+        // LMSR: creator funds the maximum possible loss b * ln(2) up front
+        if let PricingMode::Lmsr { liquidity_b } = pricing_mode {
+            let max_loss = lmsr_max_loss(liquidity_b).ok_or(MarketError::LmsrMathError)?;
+            let collateral = load_collateral(
+                &ctx.accounts.market,
+                &ctx.accounts.collateral_mint,
+                &ctx.accounts.market_vault,
+                &ctx.accounts.token_program,
+            )?;
+            let received = deposit_collateral(
+                &ctx.accounts.market,
+                collateral.as_ref(),
+                &ctx.accounts.creator,
+                ctx.accounts.creator_token_account.as_ref(),
+                &ctx.accounts.system_program,
+                max_loss,
+            )?;
+            require!(received >= max_loss, MarketError::InsufficientLiquiditySubsidy);
+            ctx.accounts.market.lmsr_subsidy = received;
+        }
 
-        // Tracking
-        market.total_bets = 0;
-        market.unique_bettors = 0;
-        market.bump = ctx.bumps.market;
+        // Off-chain metadata anchor
+        let market_metadata = &mut ctx.accounts.market_metadata;
+        market_metadata.market_id = market_id;
+        market_metadata.category = metadata.category.clone();
+        market_metadata.tags = metadata.tags;
+        market_metadata.resolution_source = metadata.resolution_source;
+        market_metadata.metadata_hash = metadata.metadata_hash;
+        market_metadata.bump = ctx.bumps.market_metadata;
 
         let title_copy = title.clone();
 
@@ -77,7 +248,17 @@ pub mod core_markets {
             market_id,
             creator: ctx.accounts.creator.key(),
             title,
+            market_kind,
+            outcome_count,
+            collateral_mint,
+            pricing_mode,
             end_date,
+            betting_closes_at: ctx.accounts.market.betting_closes_at,
+            category: metadata.category,
+            metadata_hash: metadata.metadata_hash,
+            parent_market,
+            required_outcome,
+            allowlist_root,
             timestamp: clock.unix_timestamp,
         });
 
@@ -85,14 +266,276 @@ pub mod core_markets {
         Ok(())
     }
 
+    /// Create a recurring market series
+    ///
+    /// Platform authority only, and the authority becomes the creator of every
+    /// child market. Series markets post no creator bond, so their creator fee
+    /// comes from the template (capped at GlobalParameters.high_tier_fee_bps).
+    /// The first child market ends at `first_end_date`, later ones every
+    /// `template.duration_seconds`. Spawning is funded by SOL sent to the
+    /// series PDA, which reimburses each crank's rent.
+    pub fn create_series(
+        ctx: Context<CreateSeries>,
+        series_id: u32,
+        template: SeriesTemplate,
+        first_end_date: i64,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        // PROTECTION 1: Only platform authority can create series (authorization)
+        require!(
+            ctx.accounts.creator.key() == params.authority,
+            MarketError::Unauthorized
+        );
+
+        // PROTECTION 2: Child market ids must stay clear of the flag bit
+        require!(series_id < MAX_SERIES_ID, MarketError::InvalidSeriesId);
+
+        template.validate(&params)?;
+        validate_market_duration(first_end_date - clock.unix_timestamp, &params)?;
+
+        let series = &mut ctx.accounts.series;
+        series.series_id = series_id;
+        series.creator = ctx.accounts.creator.key();
+        series.template = template;
+        series.active = true;
+        series.first_end_date = first_end_date;
+        series.last_end_date = 0;
+        series.markets_created = 0;
+        series.markets_settled = 0;
+        series.total_volume = 0;
+        series.created_at = clock.unix_timestamp;
+        series.bump = ctx.bumps.series;
+
+        emit!(SeriesCreatedEvent {
+            series_id,
+            creator: series.creator,
+            title_pattern: series.template.title_pattern.clone(),
+            duration_seconds: series.template.duration_seconds,
+            first_end_date,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Series {} created: {}", series_id, series.template.title_pattern);
+        Ok(())
+    }
+
+    /// Replace a series template and pause or resume spawning
+    ///
+    /// Series creator only. Changes apply to markets spawned afterwards.
+    pub fn update_series(
+        ctx: Context<UpdateSeries>,
+        template: SeriesTemplate,
+        active: bool,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        template.validate(&params)?;
+
+        let series = &mut ctx.accounts.series;
+        series.template = template;
+        series.active = active;
+
+        emit!(SeriesUpdatedEvent {
+            series_id: series.series_id,
+            title_pattern: series.template.title_pattern.clone(),
+            duration_seconds: series.template.duration_seconds,
+            active,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Series {} updated (active: {})", series.series_id, active);
+        Ok(())
+    }
+
+    /// Create the next market of a series
+    ///
+    /// Permissionless crank, allowed once the previous child market has ended
+    /// (the first one any time after create_series). Child markets are binary
+    /// and parimutuel, with market_id = MarketSeries::market_id(series_id, index)
+    /// and "{n}" in the title pattern replaced by index + 1. End dates stay on
+    /// the series schedule, skipping any periods the crank missed. The series
+    /// PDA reimburses the cranker for the market and metadata rent.
+    pub fn spawn_next_market(ctx: Context<SpawnNextMarket>) -> Result<()> {
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.market_creation_enabled,
+            MarketError::MarketCreationDisabled,
+        )?;
+
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        let series = &ctx.accounts.series;
+
+        // PROTECTION 1: Active series, previous child market ended
+        require!(series.active, MarketError::SeriesPaused);
+        require!(
+            series.markets_created == 0 || clock.unix_timestamp >= series.last_end_date,
+            MarketError::PreviousMarketNotEnded
+        );
+
+        let series_id = series.series_id;
+        let index = series.markets_created;
+        let market_id = series.next_market_id();
+        let creator = series.creator;
+        let template = series.template.clone();
+
+        // PROTECTION 2: Scheduled end date and rendered title within limits
+        let end_date = series.next_end_date(clock.unix_timestamp, params.min_duration_seconds);
+        validate_market_duration(end_date - clock.unix_timestamp, &params)?;
+        let title = template
+            .title_pattern
+            .replace("{n}", &(index as u64 + 1).to_string());
+        require!(title.len() <= MAX_TITLE_LEN, MarketError::InvalidTitle);
+
+        // Reimburse the cranker's rent out of the series balance
+        let rent = Rent::get()?;
+        let spawn_rent =
+            rent.minimum_balance(Market::space(0)) + rent.minimum_balance(MarketMetadata::LEN);
+        let series_info = ctx.accounts.series.to_account_info();
+        let available = series_info
+            .lamports()
+            .saturating_sub(rent.minimum_balance(series_info.data_len()));
+        require!(available >= spawn_rent, MarketError::SeriesUnderfunded);
+        **series_info.try_borrow_mut_lamports()? -= spawn_rent;
+        **ctx.accounts.cranker.try_borrow_mut_lamports()? += spawn_rent;
+
+        let market = &mut ctx.accounts.market;
+        init_market(
+            market,
+            market_id,
+            creator,
+            title.clone(),
+            template.description,
+            end_date,
+            MarketKind::Binary,
+            Vec::new(),
+            PricingMode::Parimutuel,
+            &params,
+            &clock,
+            ctx.bumps.market,
+        );
+        market.series = Some(ctx.accounts.series.key());
+        market.creator_fee_bps_override = Some(template.creator_fee_bps);
+        market.resolver = template.resolver;
+        let betting_closes_at = market.betting_closes_at;
+
+        let metadata = template.metadata;
+        let market_metadata = &mut ctx.accounts.market_metadata;
+        market_metadata.market_id = market_id;
+        market_metadata.category = metadata.category.clone();
+        market_metadata.tags = metadata.tags;
+        market_metadata.resolution_source = metadata.resolution_source;
+        market_metadata.metadata_hash = metadata.metadata_hash;
+        market_metadata.bump = ctx.bumps.market_metadata;
+
+        let series = &mut ctx.accounts.series;
+        series.markets_created = series
+            .markets_created
+            .checked_add(1)
+            .ok_or(MarketError::SeriesOverflow)?;
+        series.last_end_date = end_date;
+
+        emit!(MarketCreatedEvent {
+            market_id,
+            creator,
+            title: title.clone(),
+            market_kind: MarketKind::Binary,
+            outcome_count: 0,
+            collateral_mint: None,
+            pricing_mode: PricingMode::Parimutuel,
+            end_date,
+            betting_closes_at,
+            category: metadata.category,
+            metadata_hash: metadata.metadata_hash,
+            parent_market: None,
+            required_outcome: None,
+            allowlist_root: None,
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(SeriesMarketSpawnedEvent {
+            series_id,
+            market_id,
+            index,
+            end_date,
+            cranker: ctx.accounts.cranker.key(),
+            rent_reimbursed: spawn_rent,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Series {} spawned market {} (#{}): {}",
+            series_id,
+            market_id,
+            index + 1,
+            title
+        );
+        Ok(())
+    }
+
+    /// Close a paused series, returning its balance to the creator
+    ///
+    /// Series creator only, once every child market has been resolved or
+    /// cancelled (settling a child market updates the series stats).
+    pub fn close_series(ctx: Context<CloseSeries>) -> Result<()> {
+        let series = &ctx.accounts.series;
+        require!(
+            !series.active && series.markets_settled == series.markets_created,
+            MarketError::AccountNotClosable
+        );
+
+        emit!(SeriesClosedEvent {
+            series_id: series.series_id,
+            markets_created: series.markets_created,
+            total_volume: series.total_volume,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Series {} closed", series.series_id);
+        Ok(())
+    }
+
     /// Place a bet on a market
     ///
-    /// Transfers SOL, updates pools, calculates fees, creates UserBet account.
+    /// Transfers collateral, updates pools, calculates fees, creates UserBet account.
+    /// `fee_payer` pays the rent of new accounts and is refunded when they close;
+    /// relayers sign as fee_payer, otherwise pass the bettor.
+    /// For Token-2022 mints with transfer fees, pools and fees use the amount
+    /// actually received by the vault.
+    ///
+    /// Slippage: the bet fails unless the odds of `bet_side` after it lands are
+    /// within [min_odds_bps, max_odds_bps]. Buying a side pushes its odds up, so
+    /// max_odds_bps is the worst price the bettor accepts. Pass 0 and 10000 to
+    /// accept any odds.
+    ///
+    /// Allowlisted markets require `allowlist_proof`, the Merkle proof of the
+    /// bettor's pubkey against the market's allowlist_root (empty otherwise).
     pub fn place_bet(
         ctx: Context<PlaceBet>,
         bet_side: BetSide,
         amount: u64,
+        min_odds_bps: u16,
+        max_odds_bps: u16,
+        allowlist_proof: Vec<[u8; 32]>,
     ) -> Result<()> {
+        require!(
+            min_odds_bps <= max_odds_bps && max_odds_bps <= 10000,
+            MarketError::InvalidSlippageBounds
+        );
+
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.betting_enabled,
+            MarketError::BettingDisabled,
+        )?;
+
         // Deserialize global_parameters from parameter_storage program
         let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
         let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
@@ -106,66 +549,179 @@ pub mod core_markets {
                 MarketError::MarketNotActive
             );
             require!(
-                clock.unix_timestamp < market.end_date,
-                MarketError::MarketEnded
+                clock.unix_timestamp < market.betting_closes_at,
+                MarketError::BettingClosed
             );
+            market.validate_bet_side(&bet_side)?;
+
+            // Private markets: bettor must be in the allowlist
+            if let Some(root) = &market.allowlist_root {
+                require!(
+                    verify_merkle_proof(
+                        &allowlist_proof,
+                        root,
+                        allowlist_leaf(&ctx.accounts.bettor.key())
+                    ),
+                    MarketError::NotAllowlisted
+                );
+            }
+
+            // Conditional markets: parent unresolved or resolved to required_outcome
+            if market.parent_market.is_some() && !market.condition_met {
+                let parent = ctx
+                    .accounts
+                    .parent_market
+                    .as_ref()
+                    .ok_or(MarketError::InvalidParentMarket)?;
+                require!(
+                    market.parent_market == Some(parent.key()),
+                    MarketError::InvalidParentMarket
+                );
+                require!(!market.condition_failed(parent), MarketError::ConditionFailed);
+            }
         }
 
-        // Validate bet amount against parameters
+        // Bet and size limits: GlobalParameters for SOL, CollateralConfig for SPL mints
+        let (min_bet, max_bet, max_market_size, max_position) = bet_limits(
+            &ctx.accounts.market,
+            ctx.accounts.collateral_config.as_ref(),
+            &params,
+        )?;
+
+        // Validate bet amount against limits
         require!(
-            amount >= params.min_bet_lamports,
+            amount >= min_bet,
             MarketError::BetTooSmall
         );
         require!(
-            amount <= params.max_bet_lamports,
+            amount <= max_bet,
             MarketError::BetTooLarge
         );
 
+        // Transfer collateral from bettor to market (amount received after transfer fees)
+        let amount = {
+            let collateral = load_collateral(
+                &ctx.accounts.market,
+                &ctx.accounts.collateral_mint,
+                &ctx.accounts.market_vault,
+                &ctx.accounts.token_program,
+            )?;
+            deposit_collateral(
+                &ctx.accounts.market,
+                collateral.as_ref(),
+                &ctx.accounts.bettor,
+                ctx.accounts.bettor_token_account.as_ref(),
+                &ctx.accounts.system_program,
+                amount,
+            )?
+        };
+
         // Calculate fees (in basis points)
         let platform_fee = (amount as u128 * params.platform_fee_bps as u128) / 10000;
 
         // Story 2.11: Use tiered creator fee based on bond tier
-        // Deserialize BondEscrow to read bond_tier
-        let bond_escrow_data = ctx.accounts.bond_escrow.try_borrow_data()?;
-        let bond_escrow = BondEscrow::try_deserialize(&mut &bond_escrow_data[8..])?; // Skip 8-byte discriminator
+        // (series markets have no bond and use the template's fee instead)
+        let creator_fee_bps = match ctx.accounts.market.creator_fee_bps_override {
+            Some(fee_bps) => fee_bps,
+            None => {
+                // Deserialize BondEscrow to read bond_tier
+                let bond_escrow_data = ctx.accounts.bond_escrow.try_borrow_data()?;
+                let bond_escrow = BondEscrow::try_deserialize(&mut &bond_escrow_data[8..])?; // Skip 8-byte discriminator
+
+                get_creator_fee_bps_for_tier(
+                    bond_escrow.bond_tier,
+                    &params
+                )
+            }
+        };
+        let creator_fee = (amount as u128 * creator_fee_bps as u128) / 10000;
+        let treasury_fee = (amount as u128 * params.treasury_fee_bps as u128) / 10000;
+        let total_fees = platform_fee + creator_fee + treasury_fee;
 
-        let creator_fee_bps = get_creator_fee_bps_for_tier(
-            bond_escrow.bond_tier,
-            &params
+        // Referred bets: part of the platform fee is earmarked for the referrer
+        require!(
+            ctx.accounts.referrer.is_some() == ctx.accounts.referral_fees.is_some(),
+            MarketError::MissingReferralAccounts
         );
-        let creator_fee = (amount as u128 * creator_fee_bps as u128) / 10000;
-        let total_fees = platform_fee + creator_fee;
-        let amount_to_pool = amount - total_fees as u64;
+        let referral_fee = if ctx.accounts.referral_fees.is_some() {
+            let referral_fee_bps = params.referral_fee_bps.min(MAX_REFERRAL_FEE_BPS);
+            platform_fee * referral_fee_bps as u128 / 10000
+        } else {
+            0
+        };
+        let amount_to_pool = amount
+            .checked_sub(total_fees as u64)
+            .ok_or(MarketError::FeeOverflow)?;
 
-        // Transfer SOL from bettor to market PDA
-        anchor_lang::system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.bettor.to_account_info(),
-                    to: ctx.accounts.market.to_account_info(),
-                },
-            ),
-            amount,
-        )?;
+        // Market size limit (per-market override or global default)
+        let market_size = ctx.accounts.market.total_pool()?;
+        let headroom = max_market_size.saturating_sub(market_size);
+        if amount_to_pool > headroom {
+            // Largest gross bet whose pooled amount still fits
+            let total_fee_bps = params.platform_fee_bps as u128
+                + creator_fee_bps as u128
+                + params.treasury_fee_bps as u128;
+            let max_bet_that_fits = (headroom as u128 * 10000 / (10000 - total_fee_bps).max(1))
+                .min(u64::MAX as u128);
+            msg!(
+                "Market size limit {} reached: {} headroom remaining, max bet {}",
+                max_market_size,
+                headroom,
+                max_bet_that_fits
+            );
+            // Clients read [headroom, max bet] from the error's compared values
+            return Err(error!(MarketError::MarketSizeExceeded)
+                .with_values((headroom, max_bet_that_fits)));
+        }
+
+        // Per-wallet position limit: cumulative gross stake on this market
+        let allowance = max_position.saturating_sub(ctx.accounts.user_position.total_amount);
+        if amount > allowance {
+            msg!(
+                "Position limit {} reached: {} staked, {} remaining",
+                max_position,
+                ctx.accounts.user_position.total_amount,
+                allowance
+            );
+            // Clients read [remaining allowance, limit] from the error's compared values
+            return Err(error!(MarketError::PositionLimitExceeded)
+                .with_values((allowance, max_position)));
+        }
 
         // Now get mutable references after the transfer is complete
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
-
-        // Update pools with overflow protection
-        match bet_side {
-            BetSide::Yes => {
-                market.yes_pool = market.yes_pool
-                    .checked_add(amount_to_pool)
-                    .ok_or(MarketError::PoolOverflow)?;
-            }
-            BetSide::No => {
-                market.no_pool = market.no_pool
-                    .checked_add(amount_to_pool)
-                    .ok_or(MarketError::PoolOverflow)?;
-            }
+        let user_position = &mut ctx.accounts.user_position;
+
+        // First bet on this market: initialize the position, count a new bettor
+        if user_position.bet_count == 0 {
+            user_position.market_id = market.market_id;
+            user_position.bettor = ctx.accounts.bettor.key();
+            user_position.outcome_stakes = vec![0; market.outcome_count()];
+            user_position.created_at = clock.unix_timestamp;
+            user_position.payer = ctx.accounts.fee_payer.key();
+            user_position.bump = ctx.bumps.user_position;
+
+            market.unique_bettors = market.unique_bettors
+                .checked_add(1)
+                .ok_or(MarketError::UniqueBettorsOverflow)?;
+            market.open_child_accounts = market.open_child_accounts
+                .checked_add(1)
+                .ok_or(MarketError::TotalBetsOverflow)?;
         }
+        let bet_index = user_position.bet_count;
+        let sequence = market.total_bets;
+
+        // Child accounts must all be closed before close_market
+        market.open_child_accounts = market.open_child_accounts
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+
+        // LMSR: amount_to_pool buys shares at the cost-function price
+        let shares = market.lmsr_buy(&bet_side, amount_to_pool)?;
+
+        // Update pools with overflow protection (LMSR: collateral paid per side)
+        market.add_to_pool(&bet_side, amount_to_pool)?;
 
         // Update market stats with overflow protection
         market.total_volume = market.total_volume
@@ -182,9 +738,50 @@ pub mod core_markets {
         market.total_creator_fees = market.total_creator_fees
             .checked_add(creator_fee as u64)
             .ok_or(MarketError::FeeOverflow)?;
+        market.total_treasury_fees = market.total_treasury_fees
+            .checked_add(treasury_fee as u64)
+            .ok_or(MarketError::FeeOverflow)?;
+        market.total_referral_fees = market.total_referral_fees
+            .checked_add(referral_fee as u64)
+            .ok_or(MarketError::FeeOverflow)?;
 
-        // Calculate current odds
-        let yes_odds = calculate_odds(market.yes_pool, market.no_pool);
+        // Accrue the referrer's share (first referred bet initializes the account)
+        let referrer = ctx.accounts.referrer.as_ref().map(|referrer| referrer.key());
+        if let (Some(referral), Some(referrer)) = (ctx.accounts.referral_fees.as_mut(), referrer) {
+            if referral.bets_referred == 0 {
+                referral.market_id = market.market_id;
+                referral.referrer = referrer;
+                referral.payer = ctx.accounts.fee_payer.key();
+                referral.bump = ctx.bumps.referral_fees.ok_or(MarketError::MissingReferralAccounts)?;
+                market.open_child_accounts = market.open_child_accounts
+                    .checked_add(1)
+                    .ok_or(MarketError::TotalBetsOverflow)?;
+            }
+            referral.record_bet(amount, referral_fee as u64)?;
+        }
+
+        // Calculate current odds (one entry per outcome)
+        let outcome_odds = market.outcome_odds();
+
+        // Slippage protection: odds of the side bought must be within the user's tolerance
+        let bought_odds = outcome_odds[market.outcome_index(&bet_side)];
+        if bought_odds < min_odds_bps || bought_odds > max_odds_bps {
+            msg!(
+                "{:?} odds {} bps outside accepted range [{}, {}]",
+                bet_side,
+                bought_odds,
+                min_odds_bps,
+                max_odds_bps
+            );
+            return err!(MarketError::SlippageExceeded);
+        }
+
+        let side_odds = if market.is_lmsr() {
+            // LMSR: average execution price per share
+            (amount_to_pool as u128 * 10000 / shares as u128).min(10000) as u16
+        } else {
+            outcome_odds[market.outcome_index(&bet_side)]
+        };
 
         // Initialize UserBet account
         user_bet.market_id = market.market_id;
@@ -194,468 +791,5218 @@ pub mod core_markets {
         user_bet.amount_to_pool = amount_to_pool;
         user_bet.platform_fee = platform_fee as u64;
         user_bet.creator_fee = creator_fee as u64;
+        user_bet.treasury_fee = treasury_fee as u64;
         user_bet.timestamp = clock.unix_timestamp;
         user_bet.claimed = false;
-        user_bet.odds_at_bet = yes_odds;
+        user_bet.odds_at_bet = side_odds;
+        user_bet.shares = shares;
+        user_bet.bet_index = bet_index;
+        user_bet.sequence = sequence;
+        user_bet.voided = false;
+        user_bet.payer = ctx.accounts.fee_payer.key();
+        user_bet.referrer = referrer;
+        user_bet.referral_fee = referral_fee as u64;
         user_bet.bump = ctx.bumps.user_bet;
 
+        // Aggregate into the bettor's position
+        user_position.record_bet(
+            market.outcome_index(&bet_side),
+            amount,
+            amount_to_pool,
+            platform_fee as u64,
+            creator_fee as u64,
+            treasury_fee as u64,
+        )?;
+
         let bet_side_copy = bet_side.clone();
 
         emit!(BetPlacedEvent {
             market_id: market.market_id,
             bettor: ctx.accounts.bettor.key(),
+            bet_index,
             bet_side,
             amount,
             amount_to_pool,
             platform_fee: platform_fee as u64,
             creator_fee: creator_fee as u64,
+            treasury_fee: treasury_fee as u64,
+            referrer,
+            referral_fee: referral_fee as u64,
             yes_pool: market.yes_pool,
             no_pool: market.no_pool,
-            yes_odds,
+            outcome_odds,
+            shares,
             timestamp: clock.unix_timestamp,
         });
 
         msg!(
-            "Bet placed: {} SOL on {:?}, odds: {}%",
+            "Bet placed: {} SOL on {:?}, odds: {} bps",
             amount as f64 / 1_000_000_000.0,
             bet_side_copy,
-            yes_odds
+            side_odds
         );
 
         Ok(())
     }
 
-    /// Resolve a market (placeholder for Epic 2 integration)
+    /// Seed a new market's pools at a starting YES probability
     ///
-    /// Will be called by MarketResolution program after voting.
-    pub fn resolve_market(
-        ctx: Context<ResolveMarket>,
-        outcome: BetSide,
+    /// Market creator only, on a binary parimutuel market before its first bet.
+    /// `amount` is split into a YES bet of amount * yes_probability_bps / 10000
+    /// and a NO bet of the rest, both UserBets in the creator's position, so the
+    /// seed settles (and refunds) like any other bet. The place_bet limits apply:
+    /// each seeded bet within the min/max bet, the seed within the market size
+    /// and the creator's position limit. Only fees are waived: seeded stake pays
+    /// no platform, creator or treasury fee and is kept out of total_volume.
+    pub fn seed_liquidity(
+        ctx: Context<SeedLiquidity>,
+        amount: u64,
+        yes_probability_bps: u16,
     ) -> Result<()> {
-        // Get global parameters for platform wallet validation
-        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
-        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
-        let clock = Clock::get()?;
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.betting_enabled,
+            MarketError::BettingDisabled,
+        )?;
 
-        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
 
-        // PROTECTION 1: Only creator can resolve (authorization)
+        // Validate market: open binary parimutuel market with no bets yet
+        {
+            let market = &ctx.accounts.market;
+            require!(
+                market.status == MarketStatus::Active,
+                MarketError::MarketNotActive
+            );
+            require!(
+                clock.unix_timestamp < market.betting_closes_at,
+                MarketError::BettingClosed
+            );
+            require!(
+                market.market_kind == MarketKind::Binary && !market.is_lmsr(),
+                MarketError::InvalidSeedMarket
+            );
+            require!(market.total_bets == 0, MarketError::MarketAlreadySeeded);
+        }
         require!(
-            ctx.accounts.authority.key() == market.creator,
-            MarketError::Unauthorized
+            yes_probability_bps > 0 && yes_probability_bps < 10000,
+            MarketError::InvalidSeedProbability
         );
 
-        // PROTECTION 2: Validate platform wallet matches global parameters
-        require!(
-            ctx.accounts.platform_wallet.key() == params.authority,
-            MarketError::Unauthorized
-        );
+        // Transfer collateral from creator to market (amount received after transfer fees)
+        let amount = {
+            let collateral = load_collateral(
+                &ctx.accounts.market,
+                &ctx.accounts.collateral_mint,
+                &ctx.accounts.market_vault,
+                &ctx.accounts.token_program,
+            )?;
+            deposit_collateral(
+                &ctx.accounts.market,
+                collateral.as_ref(),
+                &ctx.accounts.creator,
+                ctx.accounts.creator_token_account.as_ref(),
+                &ctx.accounts.system_program,
+                amount,
+            )?
+        };
 
-        // PROTECTION 3: Market must be active
+        let yes_amount = (amount as u128 * yes_probability_bps as u128 / 10000) as u64;
+        let no_amount = amount - yes_amount;
+
+        // Same limits as place_bet (the market is empty, so its whole size is headroom)
+        let (min_bet, max_bet, max_market_size, max_position) = {
+            let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+            let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+            bet_limits(
+                &ctx.accounts.market,
+                ctx.accounts.collateral_config.as_ref(),
+                &params,
+            )?
+        };
         require!(
-            market.status == MarketStatus::Active,
-            MarketError::MarketAlreadyResolved
+            yes_amount.min(no_amount) >= min_bet.max(1),
+            MarketError::BetTooSmall
         );
-
-        // PROTECTION 4: Cannot resolve before end date
         require!(
-            clock.unix_timestamp >= market.end_date,
-            MarketError::MarketNotEnded
+            yes_amount.max(no_amount) <= max_bet,
+            MarketError::BetTooLarge
         );
-
-        // Update market status and outcome
-        market.status = MarketStatus::Resolved;
-        market.resolved_outcome = Some(outcome.clone());
-
-        // Copy fee amounts before transfers
-        let platform_fees = market.total_platform_fees;
-        let creator_fees = market.total_creator_fees;
-        let market_id = market.market_id;
-        let yes_pool = market.yes_pool;
-        let no_pool = market.no_pool;
-
-        // Distribute accumulated fees
-        if platform_fees > 0 {
-            **ctx.accounts.market.to_account_info().try_borrow_mut_lamports()? -= platform_fees;
-            **ctx.accounts.platform_wallet.try_borrow_mut_lamports()? += platform_fees;
+        if amount > max_market_size {
+            return Err(error!(MarketError::MarketSizeExceeded)
+                .with_values((max_market_size, max_market_size)));
         }
+        if amount > max_position {
+            return Err(error!(MarketError::PositionLimitExceeded)
+                .with_values((max_position, max_position)));
+        }
+
+        let market = &mut ctx.accounts.market;
+        let user_position = &mut ctx.accounts.user_position;
+
+        // Creator position (first bet on this market, as in place_bet)
+        user_position.market_id = market.market_id;
+        user_position.bettor = ctx.accounts.creator.key();
+        user_position.outcome_stakes = vec![0; market.outcome_count()];
+        user_position.created_at = clock.unix_timestamp;
+        user_position.payer = ctx.accounts.creator.key();
+        user_position.bump = ctx.bumps.user_position;
+        market.unique_bettors = market.unique_bettors
+            .checked_add(1)
+            .ok_or(MarketError::UniqueBettorsOverflow)?;
+        market.open_child_accounts = market.open_child_accounts
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
 
-        if creator_fees > 0 {
-            **ctx.accounts.market.to_account_info().try_borrow_mut_lamports()? -= creator_fees;
-            **ctx.accounts.creator_wallet.try_borrow_mut_lamports()? += creator_fees;
+        for (user_bet, bet_side, stake, odds, bump) in [
+            (
+                &mut ctx.accounts.yes_bet,
+                BetSide::Yes,
+                yes_amount,
+                yes_probability_bps,
+                ctx.bumps.yes_bet,
+            ),
+            (
+                &mut ctx.accounts.no_bet,
+                BetSide::No,
+                no_amount,
+                10000 - yes_probability_bps,
+                ctx.bumps.no_bet,
+            ),
+        ] {
+            user_bet.market_id = market.market_id;
+            user_bet.bettor = ctx.accounts.creator.key();
+            user_bet.bet_side = bet_side.clone();
+            user_bet.amount = stake;
+            user_bet.amount_to_pool = stake;
+            user_bet.platform_fee = 0;
+            user_bet.creator_fee = 0;
+            user_bet.treasury_fee = 0;
+            user_bet.timestamp = clock.unix_timestamp;
+            user_bet.claimed = false;
+            user_bet.odds_at_bet = odds;
+            user_bet.shares = 0;
+            user_bet.bet_index = user_position.bet_count;
+            user_bet.sequence = market.total_bets;
+            user_bet.voided = false;
+            user_bet.payer = ctx.accounts.creator.key();
+            user_bet.referrer = None;
+            user_bet.referral_fee = 0;
+            user_bet.bump = bump;
+
+            market.add_to_pool(&bet_side, stake)?;
+            market.total_bets = market.total_bets
+                .checked_add(1)
+                .ok_or(MarketError::TotalBetsOverflow)?;
+            market.open_child_accounts = market.open_child_accounts
+                .checked_add(1)
+                .ok_or(MarketError::TotalBetsOverflow)?;
+            user_position.record_bet(market.outcome_index(&bet_side), stake, stake, 0, 0, 0)?;
         }
 
-        let outcome_copy = outcome.clone();
+        market.creator_seed = amount;
+        let yes_odds = market.outcome_odds()[0];
 
-        emit!(MarketResolvedEvent {
-            market_id,
-            outcome,
-            yes_pool,
-            no_pool,
+        emit!(LiquiditySeededEvent {
+            market_id: market.market_id,
+            creator: ctx.accounts.creator.key(),
+            amount,
+            yes_amount,
+            no_amount,
+            yes_odds,
             timestamp: clock.unix_timestamp,
         });
 
         msg!(
-            "Market {} resolved: {:?} | Platform fees: {} | Creator fees: {}",
-            market_id,
-            outcome_copy,
-            platform_fees,
-            creator_fees
+            "Market {} seeded with {} ({} YES / {} NO), YES odds {} bps",
+            market.market_id,
+            amount,
+            yes_amount,
+            no_amount,
+            yes_odds
         );
 
         Ok(())
     }
 
-    /// Claim winnings after market resolution
-    pub fn claim_payout(ctx: Context<ClaimPayout>) -> Result<()> {
+    /// Sell all or part of a position before betting closes
+    ///
+    /// `stake` is the portion of the bet's pooled stake to exit. Parimutuel
+    /// positions are marked to the side's current odds against the odds at
+    /// entry; LMSR positions sell the matching shares back to the market maker
+    /// (see Market::sell_position for how gains are funded). The entry fees of
+    /// the sold portion are reversed and refunded with the sale value (referred
+    /// bets pass the referrer's ReferralFees account); the exit fee from
+    /// GlobalParameters, charged on the sale value, accrues to the platform fees.
+    pub fn sell_position(
+        ctx: Context<SellPosition>,
+        stake: u64,
+        min_proceeds: u64,
+    ) -> Result<()> {
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.betting_enabled,
+            MarketError::BettingDisabled,
+        )?;
+
+        // Deserialize global_parameters from parameter_storage program
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
 
-        // Validation checks
+        // Validate market status
         require!(
-            market.status == MarketStatus::Resolved,
-            MarketError::MarketNotResolved
+            market.status == MarketStatus::Active,
+            MarketError::MarketNotActive
+        );
+        require!(
+            clock.unix_timestamp < market.betting_closes_at,
+            MarketError::BettingClosed
         );
         require!(!user_bet.claimed, MarketError::AlreadyClaimed);
         require!(
-            user_bet.bettor == ctx.accounts.bettor.key(),
-            MarketError::Unauthorized
+            stake > 0 && stake <= user_bet.amount_to_pool,
+            MarketError::InvalidSellAmount
         );
 
-        // Check if user won
-        let won = match (&user_bet.bet_side, &market.resolved_outcome) {
-            (BetSide::Yes, Some(BetSide::Yes)) => true,
-            (BetSide::No, Some(BetSide::No)) => true,
-            _ => false,
-        };
-
-        require!(won, MarketError::BetLost);
-
-        // Get winning and losing pools
-        let winning_pool = match user_bet.bet_side {
-            BetSide::Yes => market.yes_pool,
-            BetSide::No => market.no_pool,
-        };
-        let losing_pool = match user_bet.bet_side {
-            BetSide::Yes => market.no_pool,
-            BetSide::No => market.yes_pool,
+        // Portion of the position being sold (shares and original fees scale with stake)
+        let full_exit = stake == user_bet.amount_to_pool;
+        let pro_rata = |value: u64| -> u64 {
+            if full_exit {
+                value
+            } else {
+                (value as u128 * stake as u128 / user_bet.amount_to_pool as u128) as u64
+            }
         };
+        let shares_sold = pro_rata(user_bet.shares);
+        let platform_fee_sold = pro_rata(user_bet.platform_fee);
+        let creator_fee_sold = pro_rata(user_bet.creator_fee);
+        let treasury_fee_sold = pro_rata(user_bet.treasury_fee);
+        let referral_fee_sold = pro_rata(user_bet.referral_fee);
+        let fees_refunded = platform_fee_sold + creator_fee_sold + treasury_fee_sold;
+
+        // Remove the position from the pools at the current price
+        let gross_proceeds = market.sell_position(
+            &user_bet.bet_side,
+            stake,
+            shares_sold,
+            user_bet.odds_at_bet,
+        )?;
 
-        // PROTECTION 1: Division by zero - check winning_pool > 0
+        let exit_fee = (gross_proceeds as u128 * params.exit_fee_bps as u128 / 10000) as u64;
+        let net_proceeds = gross_proceeds - exit_fee + fees_refunded;
         require!(
-            winning_pool > 0,
-            MarketError::NoWinnersCannotClaim
+            net_proceeds >= min_proceeds,
+            MarketError::SaleProceedsBelowMinimum
         );
 
-        // Calculate total pool (for conservation)
-        let total_pool = winning_pool
-            .checked_add(losing_pool)
-            .ok_or(MarketError::PoolOverflow)?;
-
-        // PROTECTION 2: Calculate payout with overflow protection (use u128)
-        let share_of_winnings = (user_bet.amount_to_pool as u128)
-            .checked_mul(losing_pool as u128)
-            .ok_or(MarketError::PayoutCalculationOverflow)?
-            .checked_div(winning_pool as u128)
-            .ok_or(MarketError::PayoutCalculationOverflow)?;
-
-        let calculated_payout = (user_bet.amount_to_pool as u128)
-            .checked_add(share_of_winnings)
-            .ok_or(MarketError::PayoutCalculationOverflow)?;
-
-        // PROTECTION 3: Prevent over-claiming with total_claimed tracking
-        let remaining_pool = total_pool
-            .checked_sub(market.total_claimed)
-            .ok_or(MarketError::TotalClaimedOverflow)?;
+        // Reverse the sold portion's entry fees (still held by the market) and
+        // accrue the exit fee; a sale is not new volume
+        if referral_fee_sold > 0 {
+            ctx.accounts
+                .referral_fees
+                .as_mut()
+                .ok_or(MarketError::MissingReferralAccounts)?
+                .reverse_bet(stake + fees_refunded, referral_fee_sold)?;
+            market.total_referral_fees = market.total_referral_fees
+                .checked_sub(referral_fee_sold)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+        market.total_platform_fees = market.total_platform_fees
+            .checked_sub(platform_fee_sold)
+            .and_then(|fees| fees.checked_add(exit_fee))
+            .ok_or(MarketError::FeeOverflow)?;
+        market.total_creator_fees = market.total_creator_fees
+            .checked_sub(creator_fee_sold)
+            .ok_or(MarketError::FeeOverflow)?;
+        market.total_treasury_fees = market.total_treasury_fees
+            .checked_sub(treasury_fee_sold)
+            .ok_or(MarketError::FeeOverflow)?;
 
-        // Cap payout to remaining pool (last claimer gets remainder)
-        let actual_payout = std::cmp::min(calculated_payout as u64, remaining_pool);
+        // Shrink the position (fully exited positions have nothing left to claim)
+        user_bet.amount_to_pool -= stake;
+        user_bet.shares -= shares_sold;
+        user_bet.platform_fee -= platform_fee_sold;
+        user_bet.creator_fee -= creator_fee_sold;
+        user_bet.treasury_fee -= treasury_fee_sold;
+        user_bet.referral_fee -= referral_fee_sold;
+        user_bet.amount = user_bet.amount.saturating_sub(stake + fees_refunded);
+        if full_exit {
+            user_bet.amount = 0;
+            user_bet.claimed = true;
+        }
 
-        // Update total_claimed BEFORE transfer (reentrancy protection)
-        market.total_claimed = market.total_claimed
-            .checked_add(actual_payout)
-            .ok_or(MarketError::TotalClaimedOverflow)?;
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.record_sale(market.outcome_index(&user_bet.bet_side), stake)?;
+        if full_exit {
+            user_position.record_settlement(net_proceeds)?;
+        }
 
-        // Copy values for event/logging before transfer
         let market_id = market.market_id;
+        let bet_side = user_bet.bet_side.clone();
+        let remaining_stake = user_bet.amount_to_pool;
+        let outcome_odds = market.outcome_odds();
+        let yes_pool = market.yes_pool;
+        let no_pool = market.no_pool;
 
-        // Mark as claimed BEFORE transfer (reentrancy protection)
-        user_bet.claimed = true;
-
-        // Transfer from market PDA to bettor
-        **ctx.accounts.market.to_account_info().try_borrow_mut_lamports()? -= actual_payout;
-        **ctx.accounts.bettor.to_account_info().try_borrow_mut_lamports()? += actual_payout;
+        // Pay the seller
+        let collateral = load_collateral(
+            &ctx.accounts.market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+        withdraw_collateral(
+            &ctx.accounts.market,
+            collateral.as_ref(),
+            &ctx.accounts.bettor.to_account_info(),
+            ctx.accounts.bettor_token_account.as_ref(),
+            net_proceeds,
+        )?;
 
-        emit!(PayoutClaimedEvent {
+        emit!(PositionSoldEvent {
             market_id,
             bettor: ctx.accounts.bettor.key(),
-            amount: actual_payout,
-            timestamp: Clock::get()?.unix_timestamp,
+            bet_side,
+            stake_sold: stake,
+            shares_sold,
+            gross_proceeds,
+            exit_fee,
+            fees_refunded,
+            net_proceeds,
+            remaining_stake,
+            yes_pool,
+            no_pool,
+            outcome_odds,
+            timestamp: clock.unix_timestamp,
         });
 
         msg!(
-            "Payout claimed: {} SOL (calculated: {} SOL, remaining: {} SOL)",
-            actual_payout as f64 / 1_000_000_000.0,
-            calculated_payout as f64 / 1_000_000_000.0,
-            remaining_pool as f64 / 1_000_000_000.0
+            "Position sold: {} of stake for {} (exit fee {}, entry fees refunded {})",
+            stake,
+            net_proceeds,
+            exit_fee,
+            fees_refunded
         );
 
         Ok(())
     }
 
-    /// Cancel a stale market (Story 2.9: Stale Market Auto-Cancellation)
+    /// Report that the event was decided at `decided_at`, voiding later bets
     ///
-    /// Authority-only instruction to mark a market as cancelled.
-    /// Used by the check-stale-markets cron job to cancel markets that:
-    /// - Have status ENDED (from Story 2.3)
-    /// - Have exceeded the stale_market_threshold (default 30 days after end_date)
+    /// Platform authority, MarketResolution resolver PDA or the market's
+    /// designated resolver (series markets) only. Bets placed at or after
+    /// `decided_at` are voided: their stake and fees are refunded on claim while earlier
+    /// bets settle normally against the remaining pools. Betting closes
+    /// immediately, and resolution waits until every late bet went through void_bet.
     ///
-    /// After cancellation, all bettors can claim 100% refunds via claim_refund.
-    pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
-        // Get global parameters for authority validation
+    /// Bets are numbered market-wide in placement order (UserBet.sequence), so the
+    /// late bets are exactly those from the first bet at or after `decided_at`.
+    /// The caller proves that boundary with `first_late_bet` (omitted when no bet
+    /// is late) and `last_early_bet` (omitted when every bet is late).
+    pub fn report_decision_time(
+        ctx: Context<ReportDecisionTime>,
+        decided_at: i64,
+    ) -> Result<()> {
         let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
         let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
         let clock = Clock::get()?;
 
         let market = &mut ctx.accounts.market;
 
-        // PROTECTION 1: Only platform authority can cancel (authorization)
+        // PROTECTION 1: Only platform authority, resolver PDA or the market's
+        // designated resolver can report (authorization)
         require!(
-            ctx.accounts.authority.key() == params.authority,
+            ctx.accounts.authority.key() == params.authority
+                || ctx.accounts.authority.key() == resolver_authority()?
+                || market.resolver == Some(ctx.accounts.authority.key()),
             MarketError::Unauthorized
         );
 
-        // PROTECTION 2: Market must be Active (cannot cancel resolved markets)
+        // PROTECTION 2: Unresolved parimutuel market, reported once
         require!(
             market.status == MarketStatus::Active,
-            MarketError::CannotCancelResolvedMarket
+            MarketError::MarketNotActive
         );
-
-        // PROTECTION 3: Market must be past end date (safety: only cancel ended markets)
+        require!(!market.is_lmsr(), MarketError::LmsrVoidNotSupported);
         require!(
-            clock.unix_timestamp >= market.end_date,
-            MarketError::CannotCancelBeforeEndDate
+            market.voided_after.is_none(),
+            MarketError::DecisionTimeAlreadyReported
+        );
+        require!(
+            decided_at >= market.created_at && decided_at <= clock.unix_timestamp,
+            MarketError::InvalidDecisionTime
         );
 
-        // Copy values for event before mutation
-        let market_id = market.market_id;
-        let yes_pool = market.yes_pool;
-        let no_pool = market.no_pool;
-        let total_bets = market.total_bets;
+        // PROTECTION 3: Boundary bets pin down the first voided sequence number
+        let void_from_bet = match &ctx.accounts.first_late_bet {
+            Some(bet) => {
+                require!(
+                    bet.market_id == market.market_id && bet.timestamp >= decided_at,
+                    MarketError::InvalidVoidBoundary
+                );
+                bet.sequence
+            }
+            None => market.total_bets,
+        };
+        if void_from_bet > 0 {
+            let bet = ctx
+                .accounts
+                .last_early_bet
+                .as_ref()
+                .ok_or(MarketError::InvalidVoidBoundary)?;
+            require!(
+                bet.market_id == market.market_id
+                    && bet.sequence == void_from_bet - 1
+                    && bet.timestamp < decided_at,
+                MarketError::InvalidVoidBoundary
+            );
+        }
 
-        // Update market status to Cancelled
-        market.status = MarketStatus::Cancelled;
+        market.voided_after = Some(decided_at);
+        market.void_from_bet = void_from_bet;
+        market.betting_closes_at = market.betting_closes_at.min(clock.unix_timestamp);
 
-        emit!(MarketCancelledEvent {
-            market_id,
-            yes_pool,
-            no_pool,
-            total_bets,
+        let bets_to_void = market.total_bets - void_from_bet;
+
+        emit!(DecisionTimeReportedEvent {
+            market_id: market.market_id,
+            decided_at,
+            void_from_bet,
+            bets_to_void,
             timestamp: clock.unix_timestamp,
         });
 
         msg!(
-            "Market {} cancelled: {} bets, {} SOL yes pool, {} SOL no pool",
-            market_id,
-            total_bets,
-            yes_pool as f64 / 1_000_000_000.0,
-            no_pool as f64 / 1_000_000_000.0
+            "Market {} decided at {}: {} bets to void from #{}",
+            market.market_id,
+            decided_at,
+            bets_to_void,
+            void_from_bet
         );
 
         Ok(())
     }
 
-    /// Claim full refund for a bet on a cancelled market (Story 2.9)
+    /// Void a bet placed at or after the reported decision time
     ///
-    /// After a market is cancelled, all bettors can claim 100% refunds of their original bet.
-    /// Refund amount = amount_to_pool + platform_fee + creator_fee (full original amount).
-    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+    /// Permissionless crank. Moves the bet's remaining stake out of the pools and
+    /// reverses its fee accruals; the bettor gets both back through claim_payout /
+    /// claim_many once the market resolves. Fees are refunded in full: referred
+    /// bets must pass the referrer's ReferralFees account, whose accrual is
+    /// reduced by the bet's referral fee.
+    pub fn void_bet(ctx: Context<VoidBet>) -> Result<()> {
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
 
-        // Validation checks
         require!(
-            market.status == MarketStatus::Cancelled,
-            MarketError::MarketNotCancelled
+            market.status == MarketStatus::Active,
+            MarketError::MarketNotActive
         );
-        require!(!user_bet.claimed, MarketError::AlreadyClaimed);
         require!(
-            user_bet.bettor == ctx.accounts.bettor.key(),
-            MarketError::Unauthorized
+            market.voided_after.is_some() && user_bet.sequence >= market.void_from_bet,
+            MarketError::BetNotVoidable
         );
+        require!(!user_bet.voided, MarketError::BetAlreadyVoided);
+
+        // Fully sold bets have no stake left; they only count towards completion
+        let stake = user_bet.amount_to_pool;
+        market.remove_from_pool(&user_bet.bet_side, stake)?;
+
+        // Fees stay in the market until resolution, so they can still be handed
+        // back in full; the referrer's share comes out of its accrual
+        if user_bet.referral_fee > 0 {
+            ctx.accounts
+                .referral_fees
+                .as_mut()
+                .ok_or(MarketError::MissingReferralAccounts)?
+                .reverse_bet(user_bet.amount, user_bet.referral_fee)?;
+            market.total_referral_fees = market.total_referral_fees
+                .checked_sub(user_bet.referral_fee)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+        let platform_fee = user_bet.platform_fee;
+        market.total_platform_fees = market.total_platform_fees
+            .checked_sub(platform_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        market.total_creator_fees = market.total_creator_fees
+            .checked_sub(user_bet.creator_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        market.total_treasury_fees = market.total_treasury_fees
+            .checked_sub(user_bet.treasury_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        let fees_refunded = platform_fee + user_bet.creator_fee + user_bet.treasury_fee;
 
-        // Calculate 100% refund (original bet amount)
-        let refund_amount = user_bet.amount;
-
-        // Update total_claimed BEFORE transfer (reentrancy protection)
-        market.total_claimed = market.total_claimed
-            .checked_add(refund_amount)
-            .ok_or(MarketError::TotalClaimedOverflow)?;
-
-        // Copy values for event/logging before transfer
-        let market_id = market.market_id;
-
-        // Mark as claimed BEFORE transfer (reentrancy protection)
-        user_bet.claimed = true;
+        market.voided_stake = market.voided_stake
+            .checked_add(stake + fees_refunded)
+            .ok_or(MarketError::PoolOverflow)?;
+        market.voided_bets = market.voided_bets
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+        user_bet.voided = true;
 
-        // Transfer from market PDA to bettor (full refund)
-        **ctx.accounts.market.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-        **ctx.accounts.bettor.to_account_info().try_borrow_mut_lamports()? += refund_amount;
+        ctx.accounts
+            .user_position
+            .record_sale(market.outcome_index(&user_bet.bet_side), stake)?;
 
-        emit!(RefundClaimedEvent {
-            market_id,
-            bettor: ctx.accounts.bettor.key(),
-            amount: refund_amount,
+        emit!(BetVoidedEvent {
+            market_id: market.market_id,
+            bettor: user_bet.bettor,
+            bet_index: user_bet.bet_index,
+            sequence: user_bet.sequence,
+            stake,
+            fees_refunded,
+            pending_voids: market.pending_voids(),
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         msg!(
-            "Refund claimed: {} SOL (100% of original bet)",
-            refund_amount as f64 / 1_000_000_000.0
+            "Voided bet #{} on market {}: {} stake + {} fees, {} bets left to void",
+            user_bet.sequence,
+            market.market_id,
+            stake,
+            fees_refunded,
+            market.pending_voids()
         );
 
         Ok(())
     }
-}
 
-// ============================================================================
-// Account Structures
-// ============================================================================
+    /// Resolve a market
+    ///
+    /// Called by MarketResolution via CPI after voting; only its resolver PDA can sign.
+    pub fn resolve_market(
+        ctx: Context<ResolveMarket>,
+        outcome: BetSide,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
 
-/// Prediction market account
-#[account]
-pub struct Market {
-    pub market_id: u64,
-    pub creator: Pubkey,
-    pub title: String,
-    pub description: String,
-    pub end_date: i64,
+        // PROTECTIONS 1-4: authorization, platform wallet, status, end date
+        validate_resolution(ctx.accounts, &clock)?;
 
-    // Liquidity pools (lamports)
-    pub yes_pool: u64,
-    pub no_pool: u64,
-    pub total_volume: u64,
+        resolve_with_outcome(ctx.accounts, outcome, &clock)?;
+        record_series_settlement(&mut ctx.accounts.series, &ctx.accounts.market)
+    }
 
-    // Fee tracking (for resolution distribution)
-    pub total_platform_fees: u64,
-    pub total_creator_fees: u64,
+    /// Resolve a scalar market with the observed value
+    ///
+    /// Long positions receive (value - lower_bound) / (upper_bound - lower_bound)
+    /// of the total pool and short positions the remainder. Values outside the
+    /// range are clamped to the bounds; if the side taking the whole pool has
+    /// no stake, GlobalParameters.no_winner_policy applies. Called by MarketResolution via CPI
+    /// (VoteChoice::ScalarValue); only its resolver PDA can sign.
+    pub fn resolve_scalar_market(
+        ctx: Context<ResolveMarket>,
+        resolved_value: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
 
-    // Payout tracking (prevents over-claiming)
-    pub total_claimed: u64,
+        // PROTECTIONS 1-4: authorization, platform wallet, status, end date
+        validate_resolution(ctx.accounts, &clock)?;
 
-    // Status
-    pub status: MarketStatus,
-    pub resolved_outcome: Option<BetSide>,
+        let market = &mut ctx.accounts.market;
 
-    // Tracking
-    pub created_at: i64,
-    pub total_bets: u64,
-    pub unique_bettors: u32,
-    pub bump: u8,
-}
+        // PROTECTION 5: Only scalar markets resolve by value
+        require!(market.is_scalar(), MarketError::NotScalarMarket);
 
-/// Individual bet position
-#[account]
-pub struct UserBet {
-    pub market_id: u64,
-    pub bettor: Pubkey,
-    pub bet_side: BetSide,
-    pub amount: u64,
-    pub amount_to_pool: u64,
-    pub platform_fee: u64,
-    pub creator_fee: u64,
-    pub timestamp: i64,
-    pub claimed: bool,
-    pub odds_at_bet: u16, // Basis points (5000 = 50%)
-    pub bump: u8,
-}
+        market.resolved_value = Some(resolved_value);
+        let long_payout_bps = market.scalar_long_payout_bps()?;
 
-// ============================================================================
-// Enums
-// ============================================================================
+        // Value at a bound: one side takes the whole pool. If nobody holds that
+        // side, apply GlobalParameters.no_winner_policy like a binary market
+        let sole_winner = match long_payout_bps {
+            10000 => Some(BetSide::Long),
+            0 => Some(BetSide::Short),
+            _ => None,
+        };
+        if let Some(side) = sole_winner {
+            if market.pool_for(&side) == 0 && market.total_pool()? > 0 {
+                resolve_without_winners(ctx.accounts, side, &clock)?;
+                return record_series_settlement(&mut ctx.accounts.series, &ctx.accounts.market);
+            }
+        }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum MarketStatus {
-    Active,
-    Resolved,
-    Cancelled,
-}
+        // Update market status
+        market.status = MarketStatus::Resolved;
+        market.settled_at = clock.unix_timestamp;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum BetSide {
-    Yes,
-    No,
-}
+        // Copy values for event before transfers
+        let market_id = market.market_id;
+        let long_pool = market.yes_pool;
+        let short_pool = market.no_pool;
 
-// Story 2.11: Bond tier enum from BondManager (for tiered creator fees)
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum BondTier {
-    Tier1,  // Lowest bond, lowest creator fee (0.5%)
-    Tier2,  // Medium bond, medium creator fee (1.0%)
-    Tier3,  // Highest bond, highest creator fee (2.0%)
-}
+        // Distribute accumulated fees
+        let (platform_fees, creator_fees, treasury_fees) = distribute_fees(ctx.accounts)?;
+        record_series_settlement(&mut ctx.accounts.series, &ctx.accounts.market)?;
 
-// Story 2.11: BondEscrow struct (minimal fields needed for reading bond_tier)
-#[account]
-pub struct BondEscrow {
-    pub creator: Pubkey,
-    pub bond_amount: u64,
-    pub market_id: u64,
-    pub status: u8, // Simplified: 0=Active, 1=Refunded, 2=PartialRefund, 3=Slashed
-    pub deposited_at: i64,
-    pub refunded_at: Option<i64>,
-    pub accumulated_fees: u64,
-    pub bond_tier: BondTier,
-    pub bump: u8,
-}
+        emit!(ScalarMarketResolvedEvent {
+            market_id,
+            resolved_value,
+            long_pool,
+            short_pool,
+            long_payout_bps,
+            timestamp: clock.unix_timestamp,
+        });
 
-// ============================================================================
-// Helper Functions
-// ============================================================================
+        msg!(
+            "Scalar market {} resolved at {} (long share: {} bps) | Platform fees: {} | Creator fees: {} | Treasury fees: {}",
+            market_id,
+            resolved_value,
+            long_payout_bps,
+            platform_fees,
+            creator_fees,
+            treasury_fees
+        );
 
-/// Calculate YES odds in basis points
-fn calculate_odds(yes_pool: u64, no_pool: u64) -> u16 {
-    let total = yes_pool + no_pool;
-    if total == 0 {
-        return 5000; // 50% if no bets yet
+        Ok(())
     }
-    ((yes_pool as u128 * 10000) / total as u128) as u16
-}
 
-/// Story 2.11: Get creator fee percentage based on bond tier
-/// Maps bond tier to corresponding fee percentage from ParameterStorage
-fn get_creator_fee_bps_for_tier(bond_tier: BondTier, params: &GlobalParameters) -> u16 {
-    match bond_tier {
-        BondTier::Tier1 => params.low_tier_fee_bps,
-        BondTier::Tier2 => params.medium_tier_fee_bps,
-        BondTier::Tier3 => params.high_tier_fee_bps,
-    }
-}
+    /// Propose resolving a market before its end date
+    ///
+    /// Platform authority, MarketResolution resolver PDA or the market's
+    /// designated resolver (series markets) only, for events decided early.
+    /// Betting and position sales halt immediately and the halt time is
+    /// recorded on the market (bets between the decision and the halt can
+    /// still be voided with report_decision_time). `outcome` None proposes
+    /// cancellation. Position holders can challenge for
+    /// GlobalParameters.early_resolution_challenge_seconds; after that an
+    /// unchallenged proposal is settled by finalize_early_resolution, and a
+    /// challenged one by the normal vote once the market ends.
+    pub fn propose_early_resolution(
+        ctx: Context<ProposeEarlyResolution>,
+        outcome: Option<BetSide>,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
 
-// ============================================================================
-// Instruction Contexts
-// ============================================================================
+        let market = &mut ctx.accounts.market;
 
-#[derive(Accounts)]
-#[instruction(market_id: u64)]
-pub struct CreateMarket<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + 8 + 32 + 132 + 516 + 8 + 8*3 + 1 + 33 + 8 + 8 + 4 + 1, // ~800 bytes
-        seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump
+        // PROTECTION 1: Only platform authority, resolver PDA or the market's
+        // designated resolver can propose (authorization)
+        require!(
+            ctx.accounts.authority.key() == params.authority
+                || ctx.accounts.authority.key() == resolver_authority()?
+                || market.resolver == Some(ctx.accounts.authority.key()),
+            MarketError::Unauthorized
+        );
+
+        // PROTECTION 2: Active market before its end date, one proposal at a time
+        require!(
+            market.status == MarketStatus::Active,
+            MarketError::MarketNotActive
+        );
+        require!(
+            clock.unix_timestamp < market.end_date,
+            MarketError::MarketEnded
+        );
+        require!(
+            market.halted_at.is_none() && !market.early_challenged,
+            MarketError::EarlyResolutionPending
+        );
+
+        // PROTECTION 3: Outcome must exist for this market kind
+        if let Some(outcome) = &outcome {
+            require!(!market.is_scalar(), MarketError::ScalarMarketRequiresValue);
+            market.validate_bet_side(outcome)?;
+        }
+
+        // Halt betting and sales now
+        market.halted_at = Some(clock.unix_timestamp);
+        market.early_outcome = outcome.clone();
+        market.betting_closes_at = market.betting_closes_at.min(clock.unix_timestamp);
+
+        let challenge_ends_at = clock
+            .unix_timestamp
+            .saturating_add(params.early_resolution_challenge_seconds);
+
+        emit!(EarlyResolutionProposedEvent {
+            market_id: market.market_id,
+            proposer: ctx.accounts.authority.key(),
+            outcome: outcome.clone(),
+            halted_at: clock.unix_timestamp,
+            challenge_ends_at,
+        });
+
+        msg!(
+            "Early resolution proposed for market {}: {:?}, challenge period ends at {}",
+            market.market_id,
+            outcome,
+            challenge_ends_at
+        );
+
+        Ok(())
+    }
+
+    /// Challenge a proposed early resolution during its challenge period
+    ///
+    /// Open to anyone holding a position in the market, and to the platform
+    /// authority. The challenger posts GlobalParameters.early_resolution_challenge_bond_lamports
+    /// in an EarlyResolutionChallenge account. The proposal can no longer be
+    /// finalized: betting stays halted and the market is settled by the normal
+    /// MarketResolution vote after its end date, after which
+    /// settle_early_resolution_challenge decides the bond.
+    pub fn challenge_early_resolution(ctx: Context<ChallengeEarlyResolution>) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        let market = &mut ctx.accounts.market;
+
+        require!(
+            market.status == MarketStatus::Active,
+            MarketError::MarketNotActive
+        );
+        let halted_at = market.halted_at.ok_or(MarketError::NoEarlyResolution)?;
+        require!(
+            clock.unix_timestamp < halted_at.saturating_add(params.early_resolution_challenge_seconds),
+            MarketError::ChallengePeriodEnded
+        );
+
+        let has_position = ctx
+            .accounts
+            .user_position
+            .as_ref()
+            .is_some_and(|position| position.bet_count > 0);
+        require!(
+            has_position || ctx.accounts.challenger.key() == params.authority,
+            MarketError::Unauthorized
+        );
+        require!(
+            !market.early_challenged,
+            MarketError::EarlyResolutionChallenged
+        );
+
+        // Bond is held by the challenge account until the vote settles the market
+        let bond = params.early_resolution_challenge_bond_lamports;
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.challenger.to_account_info(),
+                    to: ctx.accounts.challenge.to_account_info(),
+                },
+            ),
+            bond,
+        )?;
+
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.market = market.key();
+        challenge.challenger = ctx.accounts.challenger.key();
+        challenge.bond = bond;
+        challenge.created_at = clock.unix_timestamp;
+        challenge.bump = ctx.bumps.challenge;
+
+        market.early_challenged = true;
+
+        emit!(EarlyResolutionChallengedEvent {
+            market_id: market.market_id,
+            challenger: ctx.accounts.challenger.key(),
+            proposed_outcome: market.early_outcome.clone(),
+            bond,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Early resolution of market {} challenged by {} with a {} lamport bond",
+            market.market_id,
+            ctx.accounts.challenger.key(),
+            bond
+        );
+
+        Ok(())
+    }
+
+    /// Settle the bond of a challenged early resolution
+    ///
+    /// Permissionless once the market has been resolved or cancelled by the
+    /// vote. If the result matches the proposal (cancellation for a None
+    /// proposal) the challenge failed and the bond goes to the treasury;
+    /// otherwise it returns to the challenger. The challenge account's rent
+    /// returns to the challenger either way.
+    pub fn settle_early_resolution_challenge(
+        ctx: Context<SettleEarlyResolutionChallenge>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        require!(
+            market.status != MarketStatus::Active,
+            MarketError::MarketNotResolved
+        );
+
+        let proposal_upheld = match market.status {
+            MarketStatus::Cancelled => market.early_outcome.is_none(),
+            _ => market.early_outcome.is_some() && market.resolved_outcome == market.early_outcome,
+        };
+
+        let bond = ctx.accounts.challenge.bond;
+        if proposal_upheld {
+            **ctx.accounts.challenge.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += bond;
+            ctx.accounts.treasury.total_sol_collected = ctx.accounts.treasury.total_sol_collected
+                .checked_add(bond)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+        market.early_challenged = false;
+
+        emit!(EarlyResolutionChallengeSettledEvent {
+            market_id: market.market_id,
+            challenger: ctx.accounts.challenge.challenger,
+            proposed_outcome: market.early_outcome.clone(),
+            resolved_outcome: market.resolved_outcome.clone(),
+            bond_forfeited: proposal_upheld,
+            bond,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Early resolution challenge on market {} settled: bond {}",
+            market.market_id,
+            if proposal_upheld { "forfeited" } else { "returned" }
+        );
+
+        Ok(())
+    }
+
+    /// Settle a market whose early resolution went unchallenged
+    ///
+    /// Permissionless once the challenge period has passed (the signer only
+    /// pays for the transaction). Resolves to the proposed outcome, or cancels
+    /// the market if cancellation was proposed.
+    pub fn finalize_early_resolution(ctx: Context<ResolveMarket>) -> Result<()> {
+        let clock = Clock::get()?;
+        let challenge_seconds = {
+            let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+            GlobalParameters::try_deserialize(&mut &global_params_data[..])?
+                .early_resolution_challenge_seconds
+        };
+
+        let halted_at = ctx
+            .accounts
+            .market
+            .halted_at
+            .ok_or(MarketError::NoEarlyResolution)?;
+        require!(
+            !ctx.accounts.market.early_challenged,
+            MarketError::EarlyResolutionChallenged
+        );
+
+        // PROTECTIONS 2-6: platform wallet, status, challenge period, voided bets, condition
+        validate_settlement(
+            ctx.accounts,
+            halted_at.saturating_add(challenge_seconds),
+            &clock,
+            MarketError::ChallengePeriodNotEnded,
+        )?;
+
+        match ctx.accounts.market.early_outcome.clone() {
+            Some(outcome) => resolve_with_outcome(ctx.accounts, outcome, &clock)?,
+            None => mark_cancelled(&mut ctx.accounts.market, &clock),
+        }
+        record_series_settlement(&mut ctx.accounts.series, &ctx.accounts.market)
+    }
+
+    /// Claim winnings after market resolution
+    pub fn claim_payout(ctx: Context<ClaimPayout>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+
+        // Validation checks
+        require!(
+            market.status == MarketStatus::Resolved,
+            MarketError::MarketNotResolved
+        );
+        require!(!user_bet.claimed, MarketError::AlreadyClaimed);
+        require!(
+            user_bet.bettor == ctx.accounts.bettor.key(),
+            MarketError::Unauthorized
+        );
+
+        // Calculate total pool (for conservation)
+        let total_pool = market.total_pool()?;
+
+        // PROTECTIONS 1-2: Win check, division by zero, overflow (u128)
+        let calculated_payout = market.calculate_payout(user_bet)?;
+
+        // PROTECTION 3: Prevent over-claiming with total_claimed tracking
+        let remaining_pool = total_pool
+            .checked_sub(market.total_claimed)
+            .ok_or(MarketError::TotalClaimedOverflow)?;
+
+        // Voided bets are refunded from the voided stake, outside the pools;
+        // others are capped to the remaining pool (last claimer gets remainder)
+        let actual_payout = if user_bet.voided {
+            market.release_voided_stake(calculated_payout as u64)?
+        } else {
+            let payout = std::cmp::min(calculated_payout as u64, remaining_pool);
+
+            // Update total_claimed BEFORE transfer (reentrancy protection)
+            market.total_claimed = market.total_claimed
+                .checked_add(payout)
+                .ok_or(MarketError::TotalClaimedOverflow)?;
+            payout
+        };
+
+        // Copy values for event/logging before transfer
+        let market_id = market.market_id;
+
+        // Mark as claimed BEFORE transfer (reentrancy protection)
+        user_bet.claimed = true;
+        ctx.accounts.user_position.record_settlement(actual_payout)?;
+
+        // Transfer from market to bettor
+        let collateral = load_collateral(
+            &ctx.accounts.market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+        withdraw_collateral(
+            &ctx.accounts.market,
+            collateral.as_ref(),
+            &ctx.accounts.bettor.to_account_info(),
+            ctx.accounts.bettor_token_account.as_ref(),
+            actual_payout,
+        )?;
+
+        emit!(PayoutClaimedEvent {
+            market_id,
+            bettor: ctx.accounts.bettor.key(),
+            amount: actual_payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Payout claimed: {} SOL (calculated: {} SOL, remaining: {} SOL)",
+            actual_payout as f64 / 1_000_000_000.0,
+            calculated_payout as f64 / 1_000_000_000.0,
+            remaining_pool as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
+
+    /// Claim payouts and refunds for many bets in one transaction
+    ///
+    /// `remaining_accounts` holds writable (market, user_bet, user_position)
+    /// triples. Each bet follows the claim_payout / claim_refund rules, but lost
+    /// and already-claimed bets are skipped instead of failing the batch, as are
+    /// token-collateral bets (claim those individually). Any other failure
+    /// (unsettled market, malformed triple) fails it.
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        let batch_size = remaining_accounts.len() / CLAIM_MANY_ACCOUNTS_PER_BET;
+        require!(
+            batch_size > 0
+                && batch_size <= MAX_CLAIM_BATCH
+                && batch_size * CLAIM_MANY_ACCOUNTS_PER_BET == remaining_accounts.len(),
+            MarketError::InvalidClaimBatch
+        );
+        require!(
+            remaining_accounts.iter().all(|account| account.is_writable),
+            MarketError::InvalidClaimBatch
+        );
+
+        let bettor = ctx.accounts.bettor.to_account_info();
+        let mut total_paid: u64 = 0;
+        let mut payouts_claimed: u32 = 0;
+        let mut refunds_claimed: u32 = 0;
+        let mut bets_skipped: u32 = 0;
+
+        for accounts in remaining_accounts.chunks_exact(CLAIM_MANY_ACCOUNTS_PER_BET) {
+            // Re-read per entry so repeated markets see earlier claims
+            let mut market: Account<'info, Market> = Account::try_from(&accounts[0])?;
+            let mut user_bet: Account<'info, UserBet> = Account::try_from(&accounts[1])?;
+            let mut user_position: Account<'info, UserPosition> = Account::try_from(&accounts[2])?;
+            validate_claim_batch_entry(&market, &user_bet, &user_position, &bettor.key())?;
+
+            // Token collateral needs per-mint accounts; those are claimed individually
+            if market.collateral_mint.is_some()
+                || user_bet.claimed
+                || market.bet_lost(&user_bet)?
+            {
+                bets_skipped += 1;
+                continue;
+            }
+
+            let amount = match market.status {
+                MarketStatus::Resolved => {
+                    let calculated_payout = market.calculate_payout(&user_bet)?;
+                    if user_bet.voided {
+                        refunds_claimed += 1;
+                        market.release_voided_stake(calculated_payout as u64)?
+                    } else {
+                        let remaining_pool = market
+                            .total_pool()?
+                            .checked_sub(market.total_claimed)
+                            .ok_or(MarketError::TotalClaimedOverflow)?;
+                        let payout = std::cmp::min(calculated_payout as u64, remaining_pool);
+                        market.total_claimed = market.total_claimed
+                            .checked_add(payout)
+                            .ok_or(MarketError::TotalClaimedOverflow)?;
+                        payouts_claimed += 1;
+                        payout
+                    }
+                }
+                MarketStatus::Cancelled => {
+                    market.total_claimed = market.total_claimed
+                        .checked_add(user_bet.amount)
+                        .ok_or(MarketError::TotalClaimedOverflow)?;
+                    refunds_claimed += 1;
+                    user_bet.amount
+                }
+                MarketStatus::Active => return err!(MarketError::MarketNotResolved),
+            };
+
+            // Update state BEFORE transfer (reentrancy protection)
+            user_bet.claimed = true;
+            user_position.record_settlement(amount)?;
+
+            withdraw_collateral(&market, None, &bettor, None, amount)?;
+
+            market.exit(&crate::ID)?;
+            user_bet.exit(&crate::ID)?;
+            user_position.exit(&crate::ID)?;
+
+            total_paid = total_paid
+                .checked_add(amount)
+                .ok_or(MarketError::TotalClaimedOverflow)?;
+        }
+
+        emit!(BatchClaimedEvent {
+            bettor: bettor.key(),
+            payouts_claimed,
+            refunds_claimed,
+            bets_skipped,
+            total_paid,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Batch claim: {} payouts, {} refunds, {} skipped, {} SOL paid",
+            payouts_claimed,
+            refunds_claimed,
+            bets_skipped,
+            total_paid as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
+
+    /// Place a parlay: one SOL stake on 2-4 binary legs, paid only if every leg wins
+    ///
+    /// Parlays on the same leg markets share a dedicated pool
+    /// ["parlay-pool", pool_id] with pool_id = parlay_pool_id(legs); the legs are
+    /// passed as remaining accounts in ascending key order (writable when the
+    /// ticket opens a new pool) and `sides[i]` is the side taken on leg i. The
+    /// pool is parimutuel over the Yes/No combinations of its legs: winning
+    /// tickets split the whole pool. Platform and treasury fees (no creator fee)
+    /// are held in the pool until close_parlay_pool; leg pools are untouched.
+    pub fn place_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceParlay<'info>>,
+        pool_id: [u8; 32],
+        nonce: u64,
+        sides: Vec<BetSide>,
+        amount: u64,
+    ) -> Result<()> {
+        require_feature_enabled(
+            &ctx.accounts.global_toggles,
+            |toggles| toggles.betting_enabled,
+            MarketError::BettingDisabled,
+        )?;
+
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        require!(amount >= params.min_bet_lamports, MarketError::BetTooSmall);
+        require!(amount <= params.max_bet_lamports, MarketError::BetTooLarge);
+
+        // PROTECTION 1: Canonical leg set matching the pool
+        let legs = ctx.remaining_accounts;
+        require!(
+            (MIN_PARLAY_LEGS..=MAX_PARLAY_LEGS).contains(&legs.len())
+                && sides.len() == legs.len()
+                && legs.windows(2).all(|pair| pair[0].key() < pair[1].key()),
+            MarketError::InvalidParlayLegs
+        );
+        let leg_keys: Vec<Pubkey> = legs.iter().map(|leg| leg.key()).collect();
+        require!(
+            parlay_pool_id(&leg_keys) == pool_id,
+            MarketError::InvalidParlayLegs
+        );
+
+        // PROTECTION 2: Every leg is a binary market still taking bets
+        // (a new pool keeps its legs from being closed until it settles)
+        let new_pool = ctx.accounts.parlay_pool.legs.is_empty();
+        let mut combination: u8 = 0;
+        for (i, (leg, side)) in legs.iter().zip(&sides).enumerate() {
+            let mut market: Account<'info, Market> = Account::try_from(leg)?;
+            require!(
+                market.market_kind == MarketKind::Binary,
+                MarketError::InvalidParlayLegs
+            );
+            market.validate_bet_side(side)?;
+            // No membership proofs on parlays: private markets can't be legs
+            require!(market.allowlist_root.is_none(), MarketError::NotAllowlisted);
+            require!(
+                market.status == MarketStatus::Active,
+                MarketError::MarketNotActive
+            );
+            require!(
+                clock.unix_timestamp < market.betting_closes_at,
+                MarketError::BettingClosed
+            );
+            if *side == BetSide::No {
+                combination |= 1 << i;
+            }
+            if new_pool {
+                require!(leg.is_writable, MarketError::InvalidParlayLegs);
+                market.open_dependents = market.open_dependents
+                    .checked_add(1)
+                    .ok_or(MarketError::TotalBetsOverflow)?;
+                market.exit(&crate::ID)?;
+            }
+        }
+
+        // The whole amount goes to the pool; fees are held there until it closes
+        let platform_fee = (amount as u128 * params.platform_fee_bps as u128 / 10000) as u64;
+        let treasury_fee = (amount as u128 * params.treasury_fee_bps as u128 / 10000) as u64;
+        let stake = amount
+            .checked_sub(platform_fee + treasury_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bettor.to_account_info(),
+                    to: ctx.accounts.parlay_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        if new_pool {
+            pool.pool_id = pool_id;
+            pool.legs = leg_keys;
+            pool.combination_stakes = vec![0; 1 << legs.len()];
+            pool.payer = ctx.accounts.bettor.key();
+            pool.created_at = clock.unix_timestamp;
+            pool.bump = ctx.bumps.parlay_pool;
+        }
+        pool.combination_stakes[combination as usize] = pool.combination_stakes[combination as usize]
+            .checked_add(stake)
+            .ok_or(MarketError::PoolOverflow)?;
+        pool.total_stake = pool.total_stake
+            .checked_add(stake)
+            .ok_or(MarketError::PoolOverflow)?;
+        pool.platform_fees = pool.platform_fees
+            .checked_add(platform_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        pool.treasury_fees = pool.treasury_fees
+            .checked_add(treasury_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        pool.open_tickets = pool.open_tickets
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+
+        let parlay = &mut ctx.accounts.parlay;
+        parlay.pool = pool.key();
+        parlay.bettor = ctx.accounts.bettor.key();
+        parlay.sides = sides;
+        parlay.combination = combination;
+        parlay.amount = amount;
+        parlay.stake = stake;
+        parlay.nonce = nonce;
+        parlay.placed_at = clock.unix_timestamp;
+        parlay.bump = ctx.bumps.parlay;
+
+        emit!(ParlayPlacedEvent {
+            pool: pool.key(),
+            parlay: parlay.key(),
+            bettor: parlay.bettor,
+            legs: pool.legs.clone(),
+            sides: parlay.sides.clone(),
+            amount,
+            stake,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Parlay placed: {} legs, {} SOL staked",
+            pool.legs.len(),
+            stake as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
+
+    /// Claim a parlay ticket once every leg has settled
+    ///
+    /// The first claim settles the pool and must pass every leg (remaining
+    /// accounts, in pool order), each resolved or cancelled. Cancelled legs drop
+    /// out: tickets differing only on them merge and the pool is repriced over
+    /// the remaining legs. Winning tickets get stake * total_stake / winning_stake.
+    /// If every leg cancelled or no ticket holds the winning combination, every
+    /// ticket gets its full amount back, fees included. The ticket is closed
+    /// either way (rent to the bettor).
+    pub fn claim_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimParlay<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let pool = &mut ctx.accounts.parlay_pool;
+
+        if !pool.settled {
+            settle_parlay_pool_with_legs(pool, ctx.remaining_accounts, &clock)?;
+        }
+
+        let parlay = &ctx.accounts.parlay;
+        let payout = pool.payout_for(parlay)?;
+
+        pool.total_claimed = pool.total_claimed
+            .checked_add(payout)
+            .ok_or(MarketError::TotalClaimedOverflow)?;
+        pool.open_tickets = pool.open_tickets.saturating_sub(1);
+
+        if payout > 0 {
+            **pool.to_account_info().try_borrow_mut_lamports()? -= payout;
+            **ctx.accounts.bettor.to_account_info().try_borrow_mut_lamports()? += payout;
+        }
+
+        emit!(ParlayClaimedEvent {
+            pool: pool.key(),
+            parlay: parlay.key(),
+            bettor: parlay.bettor,
+            stake: parlay.stake,
+            payout,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Parlay claimed: {} SOL",
+            payout as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
+
+    /// Settle a parlay pool once every leg has settled
+    ///
+    /// Permissionless crank (claim_parlay also settles on the first claim).
+    /// Legs are passed writable as remaining accounts, in pool order; settling
+    /// releases them for close_market.
+    pub fn settle_parlay_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleParlayPool<'info>>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.parlay_pool;
+        require!(!pool.settled, MarketError::ParlayPoolAlreadySettled);
+        settle_parlay_pool_with_legs(pool, ctx.remaining_accounts, &Clock::get()?)
+    }
+
+    /// Close a settled parlay pool once every ticket is claimed
+    ///
+    /// Permissionless. Pays the held platform fee to the platform wallet and
+    /// the treasury fee to the treasury (unless the tickets were refunded);
+    /// rounding dust goes to the treasury and the rent back to the bettor who
+    /// opened the pool.
+    pub fn close_parlay_pool(ctx: Context<CloseParlayPool>) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        require!(
+            ctx.accounts.platform_wallet.key() == params.authority,
+            MarketError::Unauthorized
+        );
+
+        let pool = &ctx.accounts.parlay_pool;
+        require!(
+            pool.settled && pool.open_tickets == 0,
+            MarketError::AccountNotClosable
+        );
+
+        let pool_info = pool.to_account_info();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+        let available = pool_info.lamports().saturating_sub(rent_exempt_minimum);
+
+        // Refunded pools handed the fees back with the stakes
+        let platform_fees = if pool.refunds_all() {
+            0
+        } else {
+            pool.platform_fees.min(available)
+        };
+        let to_treasury = available - platform_fees;
+
+        if platform_fees > 0 {
+            **pool_info.try_borrow_mut_lamports()? -= platform_fees;
+            **ctx.accounts.platform_wallet.try_borrow_mut_lamports()? += platform_fees;
+        }
+        if to_treasury > 0 {
+            **pool_info.try_borrow_mut_lamports()? -= to_treasury;
+            **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += to_treasury;
+            ctx.accounts.treasury.total_sol_collected = ctx.accounts.treasury.total_sol_collected
+                .checked_add(to_treasury)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+
+        msg!(
+            "Parlay pool closed: {} platform fees paid, {} (treasury fees and dust) to treasury",
+            platform_fees,
+            to_treasury
+        );
+
+        Ok(())
+    }
+
+    /// Cancel a stale market (Story 2.9: Stale Market Auto-Cancellation)
+    ///
+    /// Authority-only instruction to mark a market as cancelled.
+    /// Used by the check-stale-markets cron job to cancel markets that:
+    /// - Have status ENDED (from Story 2.3)
+    /// - Have exceeded the stale_market_threshold (default 30 days after end_date)
+    ///
+    /// Also called by MarketResolution via CPI when the community votes Cancel.
+    ///
+    /// After cancellation, all bettors can claim 100% refunds via claim_refund.
+    pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
+        // Get global parameters for authority validation
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        let market = &mut ctx.accounts.market;
+
+        // PROTECTION 1: Only platform authority or resolver PDA can cancel (authorization)
+        require!(
+            ctx.accounts.authority.key() == params.authority
+                || ctx.accounts.authority.key() == resolver_authority()?,
+            MarketError::Unauthorized
+        );
+
+        // PROTECTION 2: Market must be Active (cannot cancel resolved markets)
+        require!(
+            market.status == MarketStatus::Active,
+            MarketError::CannotCancelResolvedMarket
+        );
+
+        // PROTECTION 3: Market must be past end date (safety: only cancel ended markets)
+        require!(
+            clock.unix_timestamp >= market.end_date,
+            MarketError::CannotCancelBeforeEndDate
+        );
+
+        // PROTECTION 4: An unchallenged early proposal settles through finalize_early_resolution
+        require!(
+            !market.early_resolution_pending(),
+            MarketError::EarlyResolutionPending
+        );
+
+        // Update market status to Cancelled
+        mark_cancelled(market, &clock);
+
+        record_series_settlement(&mut ctx.accounts.series, &ctx.accounts.market)
+    }
+
+    /// Settle a conditional market once its parent has resolved or been cancelled
+    ///
+    /// Permissionless crank. If the parent resolved to required_outcome the
+    /// market resolves normally from then on; otherwise it is cancelled at once
+    /// (before its end date if need be) and bettors claim full refunds.
+    /// Runs once per market, even one already cancelled, to release the parent
+    /// for close_market.
+    pub fn settle_condition(ctx: Context<SettleCondition>) -> Result<()> {
+        let clock = Clock::get()?;
+        let parent = &mut ctx.accounts.parent_market;
+        let market = &mut ctx.accounts.market;
+
+        require!(!market.condition_settled, MarketError::ConditionAlreadySettled);
+        require!(
+            parent.status != MarketStatus::Active,
+            MarketError::ParentNotSettled
+        );
+
+        let condition_met = !market.condition_failed(parent);
+        market.condition_settled = true;
+        if condition_met {
+            market.condition_met = true;
+        } else if market.status == MarketStatus::Active {
+            mark_cancelled(market, &clock);
+        }
+        parent.open_dependents = parent.open_dependents.saturating_sub(1);
+
+        emit!(ConditionSettledEvent {
+            market_id: market.market_id,
+            parent_market_id: parent.market_id,
+            required_outcome: market.required_outcome.clone(),
+            parent_outcome: parent.resolved_outcome.clone(),
+            condition_met,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Market {} condition on parent {}: {}",
+            market.market_id,
+            parent.market_id,
+            if condition_met { "met" } else { "failed, market cancelled" }
+        );
+
+        Ok(())
+    }
+
+    /// Claim full refund for a bet on a cancelled market (Story 2.9)
+    ///
+    /// After a market is cancelled, all bettors can claim 100% refunds of their original bet.
+    /// Refund amount = amount_to_pool + platform_fee + creator_fee + treasury_fee (full original amount).
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+
+        // Validation checks
+        require!(
+            market.status == MarketStatus::Cancelled,
+            MarketError::MarketNotCancelled
+        );
+        require!(!user_bet.claimed, MarketError::AlreadyClaimed);
+        require!(
+            user_bet.bettor == ctx.accounts.bettor.key(),
+            MarketError::Unauthorized
+        );
+
+        // Calculate 100% refund (original bet amount)
+        let refund_amount = user_bet.amount;
+
+        // Update total_claimed BEFORE transfer (reentrancy protection)
+        market.total_claimed = market.total_claimed
+            .checked_add(refund_amount)
+            .ok_or(MarketError::TotalClaimedOverflow)?;
+
+        // Copy values for event/logging before transfer
+        let market_id = market.market_id;
+
+        // Mark as claimed BEFORE transfer (reentrancy protection)
+        user_bet.claimed = true;
+        ctx.accounts.user_position.record_settlement(refund_amount)?;
+
+        // Transfer from market to bettor (full refund)
+        let collateral = load_collateral(
+            &ctx.accounts.market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+        withdraw_collateral(
+            &ctx.accounts.market,
+            collateral.as_ref(),
+            &ctx.accounts.bettor.to_account_info(),
+            ctx.accounts.bettor_token_account.as_ref(),
+            refund_amount,
+        )?;
+
+        emit!(RefundClaimedEvent {
+            market_id,
+            bettor: ctx.accounts.bettor.key(),
+            amount: refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Refund claimed: {} SOL (100% of original bet)",
+            refund_amount as f64 / 1_000_000_000.0
+        );
+
+        Ok(())
+    }
+
+    /// Return the unused LMSR subsidy and trading surplus to the creator
+    ///
+    /// Resolved: surplus = collateral held - winning shares (each redeems for 1 unit).
+    /// Cancelled: bettors are refunded in full, so the surplus is the subsidy.
+    pub fn claim_lmsr_surplus(ctx: Context<ClaimLmsrSurplus>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+
+        require!(market.is_lmsr(), MarketError::NotLmsrMarket);
+        require!(
+            !market.lmsr_surplus_claimed,
+            MarketError::AlreadyClaimed
+        );
+
+        let surplus = match market.status {
+            MarketStatus::Resolved => {
+                let winning_shares = match market.resolved_outcome {
+                    Some(BetSide::Yes) => market.yes_shares,
+                    Some(BetSide::No) => market.no_shares,
+                    _ => return err!(MarketError::InvalidOutcome),
+                };
+                market
+                    .total_pool()?
+                    .checked_sub(winning_shares)
+                    .ok_or(MarketError::LmsrMathError)?
+            }
+            MarketStatus::Cancelled => market.lmsr_subsidy,
+            MarketStatus::Active => return err!(MarketError::MarketNotResolved),
+        };
+
+        // Reserve winners' shares: surplus counts against total_claimed
+        if market.status == MarketStatus::Resolved {
+            market.total_claimed = market.total_claimed
+                .checked_add(surplus)
+                .ok_or(MarketError::TotalClaimedOverflow)?;
+        }
+        market.lmsr_surplus_claimed = true;
+        let market_id = market.market_id;
+
+        let collateral = load_collateral(
+            &ctx.accounts.market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+        withdraw_collateral(
+            &ctx.accounts.market,
+            collateral.as_ref(),
+            &ctx.accounts.creator.to_account_info(),
+            ctx.accounts.creator_token_account.as_ref(),
+            surplus,
+        )?;
+
+        emit!(LmsrSurplusClaimedEvent {
+            market_id,
+            creator: ctx.accounts.creator.key(),
+            amount: surplus,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "LMSR surplus claimed for market {}: {}",
+            market_id,
+            surplus
+        );
+
+        Ok(())
+    }
+
+    /// Claim referral fees accrued on a resolved market
+    ///
+    /// Cancelled markets refund platform fees to bettors, so nothing is owed.
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        require!(
+            ctx.accounts.market.status == MarketStatus::Resolved,
+            MarketError::MarketNotResolved
+        );
+
+        let referral = &mut ctx.accounts.referral_fees;
+        let amount = referral.accrued
+            .checked_sub(referral.claimed)
+            .ok_or(MarketError::FeeOverflow)?;
+        require!(amount > 0, MarketError::NoReferralFees);
+
+        // Mark as claimed BEFORE transfer (reentrancy protection)
+        referral.claimed = referral.accrued;
+        let market_id = referral.market_id;
+        let bets_referred = referral.bets_referred;
+
+        let collateral = load_collateral(
+            &ctx.accounts.market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+        withdraw_collateral(
+            &ctx.accounts.market,
+            collateral.as_ref(),
+            &ctx.accounts.referrer.to_account_info(),
+            ctx.accounts.referrer_token_account.as_ref(),
+            amount,
+        )?;
+
+        emit!(ReferralFeesClaimedEvent {
+            market_id,
+            referrer: ctx.accounts.referrer.key(),
+            amount,
+            bets_referred,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Referral fees claimed for market {}: {} ({} bets)",
+            market_id,
+            amount,
+            bets_referred
+        );
+
+        Ok(())
+    }
+
+    /// Register or update an SPL collateral mint
+    ///
+    /// Authority-only. Limits are in the mint's base units, replacing
+    /// min_bet_lamports/max_bet_lamports/max_market_size_lamports/
+    /// max_position_per_wallet_lamports for markets denominated in this mint.
+    pub fn configure_collateral(
+        ctx: Context<ConfigureCollateral>,
+        min_bet: u64,
+        max_bet: u64,
+        max_market_size: u64,
+        max_position_per_wallet: u64,
+        enabled: bool,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
+        // PROTECTION: Only platform authority can configure collateral
+        require!(
+            ctx.accounts.authority.key() == params.authority,
+            MarketError::Unauthorized
+        );
+        require!(
+            min_bet > 0 && min_bet <= max_bet && max_bet <= max_market_size,
+            MarketError::InvalidBetLimits
+        );
+        require!(
+            max_position_per_wallet > 0,
+            MarketError::InvalidPositionLimit
+        );
+
+        let config = &mut ctx.accounts.collateral_config;
+        config.mint = ctx.accounts.collateral_mint.key();
+        config.min_bet = min_bet;
+        config.max_bet = max_bet;
+        config.max_market_size = max_market_size;
+        config.max_position_per_wallet = max_position_per_wallet;
+        config.enabled = enabled;
+        config.bump = ctx.bumps.collateral_config;
+
+        emit!(CollateralConfiguredEvent {
+            mint: config.mint,
+            min_bet,
+            max_bet,
+            max_market_size,
+            max_position_per_wallet,
+            enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Collateral {} configured: min {} max {} size {} position {} enabled {}",
+            config.mint,
+            min_bet,
+            max_bet,
+            max_market_size,
+            max_position_per_wallet,
+            enabled
+        );
+
+        Ok(())
+    }
+
+    /// Override the maximum pool size for one market
+    ///
+    /// Authority-only. None restores the GlobalParameters / CollateralConfig default.
+    pub fn set_market_size_limit(
+        ctx: Context<SetMarketSizeLimit>,
+        max_market_size: Option<u64>,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
+        // PROTECTION: Only platform authority can override market limits
+        require!(
+            ctx.accounts.authority.key() == params.authority,
+            MarketError::Unauthorized
+        );
+        require!(
+            max_market_size != Some(0),
+            MarketError::InvalidMarketSizeLimit
+        );
+
+        let market = &mut ctx.accounts.market;
+        market.max_market_size_override = max_market_size;
+
+        emit!(MarketSizeLimitUpdatedEvent {
+            market_id: market.market_id,
+            max_market_size,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Market {} size limit override: {:?}",
+            market.market_id,
+            max_market_size
+        );
+
+        Ok(())
+    }
+
+    /// Override the per-wallet position limit for one market
+    ///
+    /// Authority-only. None restores the GlobalParameters / CollateralConfig default.
+    /// Bets already placed are unaffected; the limit applies to further bets.
+    pub fn set_position_limit(
+        ctx: Context<SetPositionLimit>,
+        max_position_per_wallet: Option<u64>,
+    ) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
+        // PROTECTION: Only platform authority can override market limits
+        require!(
+            ctx.accounts.authority.key() == params.authority,
+            MarketError::Unauthorized
+        );
+        require!(
+            max_position_per_wallet != Some(0),
+            MarketError::InvalidPositionLimit
+        );
+
+        let market = &mut ctx.accounts.market;
+        market.max_position_per_wallet_override = max_position_per_wallet;
+
+        emit!(PositionLimitUpdatedEvent {
+            market_id: market.market_id,
+            max_position_per_wallet,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Market {} position limit override: {:?}",
+            market.market_id,
+            max_position_per_wallet
+        );
+
+        Ok(())
+    }
+
+    /// Replace or clear a market's allowlist
+    ///
+    /// Market creator only, while the market is active and before its first bet.
+    pub fn set_allowlist_root(
+        ctx: Context<SetAllowlistRoot>,
+        allowlist_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        require!(
+            market.status == MarketStatus::Active,
+            MarketError::MarketNotActive
+        );
+        require!(market.total_bets == 0, MarketError::AllowlistLocked);
+
+        market.allowlist_root = allowlist_root;
+
+        emit!(AllowlistUpdatedEvent {
+            market_id: market.market_id,
+            allowlist_root,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Market {} allowlist {}",
+            market.market_id,
+            if allowlist_root.is_some() { "set" } else { "cleared" }
+        );
+
+        Ok(())
+    }
+
+    /// Create the protocol treasury PDA (authority-only, once)
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
+        // PROTECTION: Only platform authority can create the treasury
+        require!(
+            ctx.accounts.authority.key() == params.authority,
+            MarketError::Unauthorized
+        );
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_sol_collected = 0;
+        treasury.total_sol_withdrawn = 0;
+        treasury.withdrawal_count = 0;
+        treasury.created_at = Clock::get()?.unix_timestamp;
+        treasury.bump = ctx.bumps.treasury;
+
+        msg!("Treasury initialized: {}", treasury.key());
+
+        Ok(())
+    }
+
+    /// Withdraw from the protocol treasury
+    ///
+    /// Governed by GlobalParameters.authority. SOL withdrawals keep the treasury
+    /// rent-exempt; pass the mint, treasury token account, recipient token account
+    /// and token program to withdraw SPL fees instead.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+
+        // PROTECTION: Only platform authority (governance) can move treasury funds
+        require!(
+            ctx.accounts.authority.key() == params.authority,
+            MarketError::Unauthorized
+        );
+        require!(amount > 0, MarketError::InvalidWithdrawalAmount);
+
+        let clock = Clock::get()?;
+        let accounts = &ctx.accounts;
+
+        let (mint, remaining_balance) = match (
+            &accounts.collateral_mint,
+            &accounts.treasury_token_account,
+            &accounts.recipient_token_account,
+            &accounts.token_program,
+        ) {
+            (None, None, None, None) => {
+                let treasury_info = accounts.treasury.to_account_info();
+                let rent_exempt_minimum = Rent::get()?.minimum_balance(treasury_info.data_len());
+                let available = treasury_info.lamports().saturating_sub(rent_exempt_minimum);
+                require!(amount <= available, MarketError::InsufficientTreasuryBalance);
+
+                **treasury_info.try_borrow_mut_lamports()? -= amount;
+                **accounts.recipient.try_borrow_mut_lamports()? += amount;
+
+                (None, available - amount)
+            }
+            (Some(mint), Some(from), Some(to), Some(token_program)) => {
+                require!(
+                    from.mint == mint.key()
+                        && from.owner == accounts.treasury.key()
+                        && to.mint == mint.key()
+                        && to.owner == accounts.recipient.key()
+                        && *mint.to_account_info().owner == token_program.key(),
+                    MarketError::InvalidCollateralAccount
+                );
+                require!(amount <= from.amount, MarketError::InsufficientTreasuryBalance);
+
+                let signer_seeds: &[&[&[u8]]] = &[&[b"treasury", &[accounts.treasury.bump]]];
+                transfer_checked(
+                    CpiContext::new_with_signer(
+                        token_program.to_account_info(),
+                        TransferChecked {
+                            from: from.to_account_info(),
+                            mint: mint.to_account_info(),
+                            to: to.to_account_info(),
+                            authority: accounts.treasury.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    amount,
+                    mint.decimals,
+                )?;
+
+                (Some(mint.key()), from.amount - amount)
+            }
+            _ => return err!(MarketError::MissingCollateralAccounts),
+        };
+
+        let authority = accounts.authority.key();
+        let recipient = accounts.recipient.key();
+
+        let treasury = &mut ctx.accounts.treasury;
+        if mint.is_none() {
+            treasury.total_sol_withdrawn = treasury.total_sol_withdrawn
+                .checked_add(amount)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+        treasury.withdrawal_count = treasury.withdrawal_count
+            .checked_add(1)
+            .ok_or(MarketError::FeeOverflow)?;
+
+        emit!(TreasuryWithdrawalEvent {
+            authority,
+            recipient,
+            mint,
+            amount,
+            remaining_balance,
+            withdrawal_count: treasury.withdrawal_count,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Treasury withdrawal #{}: {} ({:?}) to {} | Remaining: {}",
+            treasury.withdrawal_count,
+            amount,
+            mint,
+            recipient,
+            remaining_balance
+        );
+
+        Ok(())
+    }
+
+    /// Close a finished UserBet, returning rent to whoever paid it
+    ///
+    /// Permissionless. A bet is finished once claimed/refunded/sold, or once it
+    /// can never pay out on a resolved market. Unclaimed payouts and refunds stay
+    /// claimable indefinitely (and keep close_market waiting).
+    pub fn close_user_bet(ctx: Context<CloseUserBet>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &ctx.accounts.user_bet;
+
+        let finished = user_bet.claimed || market.bet_lost(user_bet)?;
+        require!(finished, MarketError::AccountNotClosable);
+
+        market.release_child_account()?;
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.closed_count = user_position.closed_count
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+
+        msg!(
+            "Closed bet {} of {} on market {}",
+            user_bet.bet_index,
+            user_bet.bettor,
+            market.market_id
+        );
+
+        Ok(())
+    }
+
+    /// Close a UserPosition whose bets are all closed, returning rent to whoever paid it
+    ///
+    /// Permissionless; the market must no longer accept bets.
+    pub fn close_user_position(ctx: Context<CloseUserPosition>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_position = &ctx.accounts.user_position;
+
+        require!(
+            market.status != MarketStatus::Active
+                && user_position.closed_count == user_position.bet_count,
+            MarketError::AccountNotClosable
+        );
+
+        market.release_child_account()?;
+
+        msg!(
+            "Closed position of {} on market {}",
+            user_position.bettor,
+            market.market_id
+        );
+
+        Ok(())
+    }
+
+    /// Close a ReferralFees account, returning rent to the bettor who funded it
+    ///
+    /// Permissionless once the fees are claimed, voided by cancellation, or the
+    /// claim period has expired (unclaimed fees are then swept by close_market).
+    pub fn close_referral_fees(ctx: Context<CloseReferralFees>) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let market = &mut ctx.accounts.market;
+        let referral = &ctx.accounts.referral_fees;
+
+        let finished = match market.status {
+            MarketStatus::Active => false,
+            MarketStatus::Cancelled => true,
+            MarketStatus::Resolved => {
+                referral.claimed == referral.accrued
+                    || market.claims_expired(params.claim_period_seconds, now)
+            }
+        };
+        require!(finished, MarketError::AccountNotClosable);
+
+        market.release_child_account()?;
+
+        msg!(
+            "Closed referral fees of {} on market {}",
+            referral.referrer,
+            market.market_id
+        );
+
+        Ok(())
+    }
+
+    /// Close a settled market after the claim period
+    ///
+    /// Permissionless. Every UserBet, UserPosition and ReferralFees account must be
+    /// closed first, and every parlay pool using the market as a leg and every
+    /// conditional market on it must be settled. Remaining collateral (payout rounding dust, expired referral
+    /// fees) is swept to the treasury; market, metadata and vault rent return to
+    /// the creator.
+    pub fn close_market(ctx: Context<CloseMarket>) -> Result<()> {
+        let global_params_data = ctx.accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        let clock = Clock::get()?;
+
+        let market = &ctx.accounts.market;
+        require!(
+            market.claims_expired(params.claim_period_seconds, clock.unix_timestamp),
+            MarketError::ClaimPeriodNotEnded
+        );
+        require!(
+            market.open_child_accounts == 0,
+            MarketError::MarketHasOpenAccounts
+        );
+        // Parlay pools and conditional markets still need this market's outcome
+        require!(
+            market.open_dependents == 0,
+            MarketError::MarketHasDependents
+        );
+        // A challenge bond is still held against this market's result
+        require!(
+            !market.early_challenged,
+            MarketError::EarlyResolutionChallenged
+        );
+
+        let collateral = load_collateral(
+            market,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.token_program,
+        )?;
+
+        let dust = match collateral.as_ref() {
+            None => {
+                let market_info = market.to_account_info();
+                let rent_exempt_minimum = Rent::get()?.minimum_balance(market_info.data_len());
+                market_info.lamports().saturating_sub(rent_exempt_minimum)
+            }
+            Some(collateral) => collateral.vault.amount,
+        };
+
+        withdraw_collateral(
+            market,
+            collateral.as_ref(),
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            dust,
+        )?;
+
+        // Vault rent goes back to the creator, who paid it in create_market
+        if let Some(collateral) = collateral.as_ref() {
+            let market_id_bytes = market.market_id.to_le_bytes();
+            let signer_seeds: &[&[&[u8]]] = &[&[b"market", market_id_bytes.as_ref(), &[market.bump]]];
+            close_account(CpiContext::new_with_signer(
+                collateral.token_program.to_account_info(),
+                CloseAccount {
+                    account: collateral.vault.to_account_info(),
+                    destination: ctx.accounts.creator.to_account_info(),
+                    authority: market.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        let market_id = market.market_id;
+        let mint = market.collateral_mint;
+        if mint.is_none() {
+            ctx.accounts.treasury.total_sol_collected = ctx.accounts.treasury.total_sol_collected
+                .checked_add(dust)
+                .ok_or(MarketError::FeeOverflow)?;
+        }
+
+        emit!(MarketClosedEvent {
+            market_id,
+            creator: ctx.accounts.creator.key(),
+            mint,
+            dust_swept: dust,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Market {} closed: {} swept to treasury ({:?})",
+            market_id,
+            dust,
+            mint
+        );
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Prediction market account
+#[account]
+pub struct Market {
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub title: String,
+    pub description: String,
+    pub end_date: i64,
+    pub betting_closes_at: i64, // end_date minus GlobalParameters.betting_cutoff_seconds
+
+    // Liquidity pools (lamports)
+    pub yes_pool: u64,
+    pub no_pool: u64,
+    pub total_volume: u64,
+    pub creator_seed: u64, // Stake added by seed_liquidity (not in total_volume)
+
+    // Categorical outcomes (empty for binary markets)
+    pub market_kind: MarketKind,
+    pub outcome_labels: Vec<String>,
+    pub outcome_pools: Vec<u64>,
+
+    // Collateral (None = native SOL held as lamports by the market PDA)
+    pub collateral_mint: Option<Pubkey>,
+    pub collateral_vault: Option<Pubkey>,
+
+    // Pool size limit override (None = GlobalParameters / CollateralConfig default)
+    pub max_market_size_override: Option<u64>,
+
+    // Per-wallet position limit override (None = GlobalParameters / CollateralConfig default)
+    pub max_position_per_wallet_override: Option<u64>,
+
+    // Recurring series (all None for standalone markets)
+    pub series: Option<Pubkey>,                 // MarketSeries that spawned this market
+    pub creator_fee_bps_override: Option<u16>,  // Replaces the bond-tier creator fee
+    pub resolver: Option<Pubkey>,               // Designated resolver from the series template
+
+    // Conditional market (None = unconditional)
+    pub parent_market: Option<Pubkey>,      // Market whose outcome this one depends on
+    pub required_outcome: Option<BetSide>,  // Parent outcome this market assumes
+    pub condition_met: bool,                // Parent resolved to required_outcome (settle_condition)
+    pub condition_settled: bool,            // settle_condition ran (parent released)
+
+    // Private market: Merkle root of allowed bettors (None = open to all)
+    pub allowlist_root: Option<[u8; 32]>,
+
+    // Pricing (LMSR fields stay zero for parimutuel markets)
+    pub pricing_mode: PricingMode,
+    pub yes_shares: u64,
+    pub no_shares: u64,
+    pub lmsr_subsidy: u64,
+    pub lmsr_surplus_claimed: bool,
+
+    // Stake left behind by discounted position sales (paid out with the losing pools)
+    pub forfeited_stake: u64,
+
+    // Fee tracking (for resolution distribution)
+    pub total_platform_fees: u64,
+    pub total_creator_fees: u64,
+    pub total_treasury_fees: u64,
+    pub total_referral_fees: u64, // Portion of total_platform_fees owed to referrers
+
+    // Payout tracking (prevents over-claiming)
+    pub total_claimed: u64,
+
+    // Retroactive void: bets with sequence >= void_from_bet are refunded
+    pub voided_after: Option<i64>, // Reported decision time
+    pub void_from_bet: u64,        // First voided UserBet.sequence
+    pub voided_bets: u64,          // Late bets processed by void_bet
+    pub voided_stake: u64,         // Voided stake held outside the pools, not yet refunded
+
+    // Early resolution: betting halted at halted_at pending early_outcome
+    pub halted_at: Option<i64>,           // Set once a proposal is made
+    pub early_outcome: Option<BetSide>,   // Proposed outcome (None = cancel)
+    pub early_challenged: bool,           // Challenged: settled by vote, bond held
+
+    // Status
+    pub status: MarketStatus,
+    pub resolved_outcome: Option<BetSide>,
+    pub resolved_value: Option<i64>, // Scalar markets only
+
+    // Tracking
+    pub created_at: i64,
+    pub settled_at: i64, // Resolution/cancellation time (0 while active); starts the claim period
+    pub total_bets: u64,
+    pub unique_bettors: u32,
+    pub open_child_accounts: u64, // UserBet/UserPosition/ReferralFees accounts not yet closed
+    pub open_dependents: u32,     // Unsettled parlay pools and conditional markets reading this one
+    pub bump: u8,
+}
+
+impl Market {
+    /// Account size for a market with `outcome_count` categorical outcomes
+    pub fn space(outcome_count: usize) -> usize {
+        8 + // discriminator
+        8 + // market_id
+        32 + // creator
+        4 + MAX_TITLE_LEN + // title
+        4 + MAX_DESCRIPTION_LEN + // description
+        8 + // end_date
+        8 + // betting_closes_at
+        8 * 3 + // yes_pool, no_pool, total_volume
+        8 + // creator_seed
+        1 + 8 * 2 + // market_kind (scalar bounds)
+        4 + outcome_count * (4 + MAX_OUTCOME_LABEL_LEN) + // outcome_labels
+        4 + outcome_count * 8 + // outcome_pools
+        33 * 2 + // collateral_mint, collateral_vault
+        9 + // max_market_size_override
+        9 + // max_position_per_wallet_override
+        33 + // series
+        3 + // creator_fee_bps_override
+        33 + // resolver
+        33 + // parent_market
+        1 + 2 + // required_outcome (Option<BetSide>)
+        1 + // condition_met
+        1 + // condition_settled
+        33 + // allowlist_root
+        1 + 8 + // pricing_mode (liquidity_b)
+        8 * 3 + // yes_shares, no_shares, lmsr_subsidy
+        1 + // lmsr_surplus_claimed
+        8 + // forfeited_stake
+        8 * 4 + // total_platform_fees, total_creator_fees, total_treasury_fees, total_referral_fees
+        8 + // total_claimed
+        1 + 8 + // voided_after
+        8 * 3 + // void_from_bet, voided_bets, voided_stake
+        1 + 8 + // halted_at
+        1 + 2 + // early_outcome (Option<BetSide>)
+        1 + // early_challenged
+        1 + // status
+        1 + 2 + // resolved_outcome (Option<BetSide>)
+        1 + 8 + // resolved_value
+        8 + // created_at
+        8 + // settled_at
+        8 + // total_bets
+        4 + // unique_bettors
+        8 + // open_child_accounts
+        4 + // open_dependents
+        1 // bump
+    }
+
+    /// Number of outcomes a bet can be placed on
+    pub fn outcome_count(&self) -> usize {
+        match self.market_kind {
+            MarketKind::Binary | MarketKind::Scalar { .. } => 2,
+            MarketKind::Categorical => self.outcome_pools.len(),
+        }
+    }
+
+    /// Whether the claim period after resolution/cancellation has elapsed
+    pub fn claims_expired(&self, claim_period_seconds: i64, now: i64) -> bool {
+        self.status != MarketStatus::Active
+            && now >= self.settled_at.saturating_add(claim_period_seconds)
+    }
+
+    /// Late bets still waiting for void_bet (resolution is blocked until zero)
+    pub fn pending_voids(&self) -> u64 {
+        if self.voided_after.is_none() {
+            return 0;
+        }
+        self.total_bets
+            .saturating_sub(self.void_from_bet)
+            .saturating_sub(self.voided_bets)
+    }
+
+    /// Early proposal open and unchallenged (only finalize_early_resolution may settle)
+    pub fn early_resolution_pending(&self) -> bool {
+        self.halted_at.is_some() && !self.early_challenged
+    }
+
+    /// Pay `amount` of voided stake back to a bettor, returning the amount
+    pub fn release_voided_stake(&mut self, amount: u64) -> Result<u64> {
+        self.voided_stake = self.voided_stake
+            .checked_sub(amount)
+            .ok_or(MarketError::PoolUnderflow)?;
+        Ok(amount)
+    }
+
+    /// One child account (UserBet, UserPosition, ReferralFees) was closed
+    pub fn release_child_account(&mut self) -> Result<()> {
+        self.open_child_accounts = self.open_child_accounts
+            .checked_sub(1)
+            .ok_or(MarketError::PoolUnderflow)?;
+        Ok(())
+    }
+
+    /// Whether this market prices shares with the LMSR cost function
+    pub fn is_lmsr(&self) -> bool {
+        matches!(self.pricing_mode, PricingMode::Lmsr { .. })
+    }
+
+    /// LMSR: buy shares of a (validated) binary side for `cost`
+    ///
+    /// Returns the shares issued; parimutuel markets issue none.
+    pub fn lmsr_buy(&mut self, side: &BetSide, cost: u64) -> Result<u64> {
+        let PricingMode::Lmsr { liquidity_b } = self.pricing_mode else {
+            return Ok(0);
+        };
+        let (side_shares, other_shares) = match side {
+            BetSide::Yes => (&mut self.yes_shares, self.no_shares),
+            BetSide::No => (&mut self.no_shares, self.yes_shares),
+            _ => return err!(MarketError::InvalidOutcome),
+        };
+
+        let shares = lmsr_shares_for_cost(*side_shares, other_shares, liquidity_b, cost)
+            .ok_or(MarketError::LmsrTradeTooLarge)?;
+        require!(shares > 0, MarketError::BetTooSmall);
+
+        *side_shares = side_shares
+            .checked_add(shares)
+            .ok_or(MarketError::PoolOverflow)?;
+        Ok(shares)
+    }
+
+    /// Conditional markets: the parent settled in a way that rules out required_outcome
+    pub fn condition_failed(&self, parent: &Market) -> bool {
+        match parent.status {
+            MarketStatus::Active => false,
+            MarketStatus::Resolved => parent.resolved_outcome != self.required_outcome,
+            MarketStatus::Cancelled => true,
+        }
+    }
+
+    /// Whether this market resolves to a numeric value
+    pub fn is_scalar(&self) -> bool {
+        matches!(self.market_kind, MarketKind::Scalar { .. })
+    }
+
+    /// Validate that a bet side (or resolution outcome) exists in this market
+    pub fn validate_bet_side(&self, side: &BetSide) -> Result<()> {
+        let valid = match (&self.market_kind, side) {
+            (MarketKind::Binary, BetSide::Yes | BetSide::No) => true,
+            (MarketKind::Categorical, BetSide::Outcome(index)) => {
+                (*index as usize) < self.outcome_pools.len()
+            }
+            (MarketKind::Scalar { .. }, BetSide::Long | BetSide::Short) => true,
+            _ => false,
+        };
+        require!(valid, MarketError::InvalidOutcome);
+        Ok(())
+    }
+
+    /// Position of a (validated) bet side in the per-outcome odds vector
+    pub fn outcome_index(&self, side: &BetSide) -> usize {
+        match side {
+            BetSide::Yes | BetSide::Long => 0,
+            BetSide::No | BetSide::Short => 1,
+            BetSide::Outcome(index) => *index as usize,
+        }
+    }
+
+    /// Pool backing a (validated) bet side
+    ///
+    /// Scalar markets keep long stake in yes_pool and short stake in no_pool.
+    pub fn pool_for(&self, side: &BetSide) -> u64 {
+        match side {
+            BetSide::Yes | BetSide::Long => self.yes_pool,
+            BetSide::No | BetSide::Short => self.no_pool,
+            BetSide::Outcome(index) => self.outcome_pools[*index as usize],
+        }
+    }
+
+    /// Add stake to the pool backing a (validated) bet side
+    pub fn add_to_pool(&mut self, side: &BetSide, amount: u64) -> Result<()> {
+        let pool = match side {
+            BetSide::Yes | BetSide::Long => &mut self.yes_pool,
+            BetSide::No | BetSide::Short => &mut self.no_pool,
+            BetSide::Outcome(index) => &mut self.outcome_pools[*index as usize],
+        };
+        *pool = pool.checked_add(amount).ok_or(MarketError::PoolOverflow)?;
+        Ok(())
+    }
+
+    /// Remove stake from the pool backing a (validated) bet side
+    pub fn remove_from_pool(&mut self, side: &BetSide, amount: u64) -> Result<()> {
+        let pool = match side {
+            BetSide::Yes | BetSide::Long => &mut self.yes_pool,
+            BetSide::No | BetSide::Short => &mut self.no_pool,
+            BetSide::Outcome(index) => &mut self.outcome_pools[*index as usize],
+        };
+        *pool = pool.checked_sub(amount).ok_or(MarketError::PoolUnderflow)?;
+        Ok(())
+    }
+
+    /// Take a sold position out of the pools, returning its gross sale value
+    ///
+    /// Parimutuel: stake marked to the side's current odds against the odds at
+    /// entry; a discount stays in the market as forfeited stake for the winners.
+    /// LMSR: cost-function proceeds for the shares; the market maker's gain or
+    /// loss against the stake is booked to the subsidy.
+    /// The pools must keep covering every remaining bet's refund should the
+    /// market be cancelled, so a gain over the stake is paid only out of
+    /// forfeited stake (parimutuel) or the subsidy (LMSR) and capped by it.
+    pub fn sell_position(
+        &mut self,
+        side: &BetSide,
+        stake: u64,
+        shares: u64,
+        odds_at_bet: u16,
+    ) -> Result<u64> {
+        let PricingMode::Lmsr { liquidity_b } = self.pricing_mode else {
+            let current_odds = self.outcome_odds()[self.outcome_index(side)];
+            let value = if odds_at_bet == 0 {
+                stake
+            } else {
+                (stake as u128 * current_odds as u128 / odds_at_bet as u128)
+                    .min(stake as u128 + self.forfeited_stake as u128) as u64
+            };
+
+            self.remove_from_pool(side, stake)?;
+            if value <= stake {
+                self.forfeited_stake = self.forfeited_stake
+                    .checked_add(stake - value)
+                    .ok_or(MarketError::PoolOverflow)?;
+            } else {
+                self.forfeited_stake -= value - stake;
+            }
+            return Ok(value);
+        };
+
+        let (side_shares, other_shares) = match side {
+            BetSide::Yes => (&mut self.yes_shares, self.no_shares),
+            BetSide::No => (&mut self.no_shares, self.yes_shares),
+            _ => return err!(MarketError::InvalidOutcome),
+        };
+        let proceeds = lmsr_proceeds_for_shares(*side_shares, other_shares, liquidity_b, shares)
+            .ok_or(MarketError::LmsrMathError)?
+            .min(stake.saturating_add(self.lmsr_subsidy));
+        *side_shares -= shares;
+
+        self.remove_from_pool(side, stake)?;
+        if proceeds <= stake {
+            self.lmsr_subsidy = self.lmsr_subsidy
+                .checked_add(stake - proceeds)
+                .ok_or(MarketError::PoolOverflow)?;
+        } else {
+            self.lmsr_subsidy -= proceeds - stake;
+        }
+        Ok(proceeds)
+    }
+
+    /// Sum of all outcome pools
+    ///
+    /// Includes stake forfeited by position sales; LMSR markets also include
+    /// the creator subsidy backing share redemption.
+    pub fn total_pool(&self) -> Result<u64> {
+        if self.is_lmsr() {
+            return self
+                .yes_pool
+                .checked_add(self.no_pool)
+                .and_then(|total| total.checked_add(self.lmsr_subsidy))
+                .ok_or(error!(MarketError::PoolOverflow));
+        }
+        let pools = match self.market_kind {
+            MarketKind::Binary | MarketKind::Scalar { .. } => {
+                self.yes_pool.checked_add(self.no_pool)
+            }
+            MarketKind::Categorical => self
+                .outcome_pools
+                .iter()
+                .try_fold(0u64, |total, pool| total.checked_add(*pool)),
+        };
+        pools
+            .and_then(|total| total.checked_add(self.forfeited_stake))
+            .ok_or(error!(MarketError::PoolOverflow))
+    }
+
+    /// Current odds in basis points, one entry per outcome
+    ///
+    /// Binary markets return [yes, no], scalar markets [long, short];
+    /// categorical markets return each outcome's share of the total pool.
+    pub fn outcome_odds(&self) -> Vec<u16> {
+        if let PricingMode::Lmsr { liquidity_b } = self.pricing_mode {
+            let yes_price = lmsr_price_bps(self.yes_shares, self.no_shares, liquidity_b);
+            return vec![yes_price, 10000 - yes_price];
+        }
+        match self.market_kind {
+            MarketKind::Binary | MarketKind::Scalar { .. } => {
+                let yes_odds = calculate_odds(self.yes_pool, self.no_pool);
+                vec![yes_odds, 10000 - yes_odds]
+            }
+            MarketKind::Categorical => calculate_categorical_odds(&self.outcome_pools),
+        }
+    }
+
+    /// Share of the total pool owed to long positions, in basis points
+    ///
+    /// Linear in the resolved value, clamped at the market bounds.
+    pub fn scalar_long_payout_bps(&self) -> Result<u16> {
+        let (lower_bound, upper_bound) = match self.market_kind {
+            MarketKind::Scalar { lower_bound, upper_bound } => (lower_bound, upper_bound),
+            _ => return err!(MarketError::NotScalarMarket),
+        };
+        let value = self
+            .resolved_value
+            .ok_or(MarketError::MarketNotResolved)?
+            .clamp(lower_bound, upper_bound);
+
+        let numerator = (value as i128 - lower_bound as i128) as u128;
+        let range = (upper_bound as i128 - lower_bound as i128) as u128;
+        Ok((numerator * 10000 / range) as u16)
+    }
+
+    /// Whether a bet can never pay out: its market resolved against it
+    ///
+    /// Mirrors the BetLost cases of calculate_payout. Voided bets, bets on
+    /// unresolved or cancelled markets are never lost.
+    pub fn bet_lost(&self, user_bet: &UserBet) -> Result<bool> {
+        if self.status != MarketStatus::Resolved || user_bet.voided {
+            return Ok(false);
+        }
+
+        if self.is_scalar() {
+            let long_bps = self.scalar_long_payout_bps()? as u128;
+            let side_bps = match user_bet.bet_side {
+                BetSide::Long => long_bps,
+                _ => 10000 - long_bps,
+            };
+            return Ok(self.total_pool()? as u128 * side_bps / 10000 == 0);
+        }
+
+        Ok(self.resolved_outcome.as_ref() != Some(&user_bet.bet_side))
+    }
+
+    /// Gross payout owed to a bet on a resolved market (before over-claim capping)
+    ///
+    /// Binary/categorical: stake + pro-rata share of all losing pools.
+    /// Scalar: stake's pro-rata share of its side's portion of the total pool.
+    /// LMSR: each winning share redeems for exactly 1 unit.
+    /// Voided bets get their remaining stake and fees (as reversed by void_bet)
+    /// back whatever the outcome.
+    pub fn calculate_payout(&self, user_bet: &UserBet) -> Result<u128> {
+        if user_bet.voided {
+            return Ok(user_bet.amount_to_pool as u128
+                + user_bet.platform_fee as u128
+                + user_bet.creator_fee as u128
+                + user_bet.treasury_fee as u128);
+        }
+
+        if self.is_lmsr() {
+            let won = self.resolved_outcome.as_ref() == Some(&user_bet.bet_side);
+            require!(won, MarketError::BetLost);
+            return Ok(user_bet.shares as u128);
+        }
+
+        let total_pool = self.total_pool()?;
+        let side_pool = self.pool_for(&user_bet.bet_side);
+
+        if self.is_scalar() {
+            let long_bps = self.scalar_long_payout_bps()? as u128;
+            let side_bps = match user_bet.bet_side {
+                BetSide::Long => long_bps,
+                _ => 10000 - long_bps,
+            };
+            let side_payout_pool = (total_pool as u128)
+                .checked_mul(side_bps)
+                .ok_or(MarketError::PayoutCalculationOverflow)?
+                / 10000;
+
+            require!(side_payout_pool > 0, MarketError::BetLost);
+            require!(side_pool > 0, MarketError::NoWinnersCannotClaim);
+
+            return (user_bet.amount_to_pool as u128)
+                .checked_mul(side_payout_pool)
+                .ok_or(MarketError::PayoutCalculationOverflow)?
+                .checked_div(side_pool as u128)
+                .ok_or(error!(MarketError::PayoutCalculationOverflow));
+        }
+
+        // Check if user won
+        let won = self.resolved_outcome.as_ref() == Some(&user_bet.bet_side);
+        require!(won, MarketError::BetLost);
+
+        // All losing pools are split pro-rata among the winning pool
+        let winning_pool = side_pool;
+        let losing_pool = total_pool - winning_pool;
+
+        // Division by zero - check winning_pool > 0
+        require!(
+            winning_pool > 0,
+            MarketError::NoWinnersCannotClaim
+        );
+
+        let share_of_winnings = (user_bet.amount_to_pool as u128)
+            .checked_mul(losing_pool as u128)
+            .ok_or(MarketError::PayoutCalculationOverflow)?
+            .checked_div(winning_pool as u128)
+            .ok_or(MarketError::PayoutCalculationOverflow)?;
+
+        (user_bet.amount_to_pool as u128)
+            .checked_add(share_of_winnings)
+            .ok_or(error!(MarketError::PayoutCalculationOverflow))
+    }
+}
+
+/// Individual bet position
+#[account]
+pub struct UserBet {
+    pub market_id: u64,
+    pub bettor: Pubkey,
+    pub bet_side: BetSide,
+    pub amount: u64,
+    pub amount_to_pool: u64,
+    pub platform_fee: u64,
+    pub creator_fee: u64,
+    pub treasury_fee: u64,
+    pub timestamp: i64,
+    pub claimed: bool,
+    pub odds_at_bet: u16, // Basis points (5000 = 50%); LMSR: average price paid
+    pub shares: u64,      // LMSR markets only: shares redeemable for 1 unit each
+    pub bet_index: u64,   // Bettor's nth bet on this market (UserPosition.bet_count at placement)
+    pub sequence: u64,    // Market-wide placement order (Market.total_bets at placement)
+    pub voided: bool,     // Placed after the reported decision time; stake refunded on claim
+    pub payer: Pubkey,    // Funded the rent (bettor, or a relayer as fee_payer)
+    pub referrer: Option<Pubkey>, // Referred bets only
+    pub referral_fee: u64,        // Part of platform_fee accrued to the referrer
+    pub bump: u8,
+}
+
+impl UserBet {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // market_id
+        32 + // bettor
+        2 + // bet_side
+        8 + // amount
+        8 + // amount_to_pool
+        8 + // platform_fee
+        8 + // creator_fee
+        8 + // treasury_fee
+        8 + // timestamp
+        1 + // claimed
+        2 + // odds_at_bet
+        8 + // shares
+        8 + // bet_index
+        8 + // sequence
+        1 + // voided
+        32 + // payer
+        1 + 32 + // referrer (Option<Pubkey>)
+        8 + // referral_fee
+        1; // bump
+}
+
+/// A bettor's aggregate position in one market: ["user-position", market, bettor]
+///
+/// Bets are enumerable as UserBet PDAs with bet_index 0..bet_count.
+#[account]
+pub struct UserPosition {
+    pub market_id: u64,
+    pub bettor: Pubkey,
+    pub outcome_stakes: Vec<u64>, // Pooled stake per outcome ([yes, no] for binary)
+    pub total_amount: u64,        // Gross amount bet, including fees
+    pub total_platform_fees: u64,
+    pub total_creator_fees: u64,
+    pub total_treasury_fees: u64,
+    pub bet_count: u64,
+    pub settled_count: u64,       // Bets claimed, refunded or fully sold
+    pub closed_count: u64,        // UserBet accounts closed (close_user_bet)
+    pub total_claimed: u64,       // Payouts, refunds and full-exit sale proceeds
+    pub created_at: i64,
+    pub payer: Pubkey,            // Funded the rent on the first bet (bettor or relayer)
+    pub bump: u8,
+}
+
+impl UserPosition {
+    pub fn space(outcome_count: usize) -> usize {
+        8 + // discriminator
+        8 + // market_id
+        32 + // bettor
+        4 + 8 * outcome_count + // outcome_stakes
+        8 * 4 + // total_amount, total_platform_fees, total_creator_fees, total_treasury_fees
+        8 * 4 + // bet_count, settled_count, closed_count, total_claimed
+        8 + // created_at
+        32 + // payer
+        1 // bump
+    }
+
+    /// Whether every bet in this position has been claimed, refunded or sold
+    pub fn is_settled(&self) -> bool {
+        self.settled_count == self.bet_count
+    }
+
+    /// Add a newly placed bet to the position
+    pub fn record_bet(
+        &mut self,
+        outcome_index: usize,
+        amount: u64,
+        amount_to_pool: u64,
+        platform_fee: u64,
+        creator_fee: u64,
+        treasury_fee: u64,
+    ) -> Result<()> {
+        let stake = &mut self.outcome_stakes[outcome_index];
+        *stake = stake
+            .checked_add(amount_to_pool)
+            .ok_or(MarketError::PoolOverflow)?;
+        self.total_amount = self.total_amount
+            .checked_add(amount)
+            .ok_or(MarketError::TotalVolumeOverflow)?;
+        self.total_platform_fees = self.total_platform_fees
+            .checked_add(platform_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        self.total_creator_fees = self.total_creator_fees
+            .checked_add(creator_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        self.total_treasury_fees = self.total_treasury_fees
+            .checked_add(treasury_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        self.bet_count = self.bet_count
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+        Ok(())
+    }
+
+    /// Remove sold stake from the position
+    pub fn record_sale(&mut self, outcome_index: usize, stake: u64) -> Result<()> {
+        let outcome_stake = &mut self.outcome_stakes[outcome_index];
+        *outcome_stake = outcome_stake
+            .checked_sub(stake)
+            .ok_or(MarketError::PoolUnderflow)?;
+        Ok(())
+    }
+
+    /// Mark one bet as settled, paying out `amount`
+    pub fn record_settlement(&mut self, amount: u64) -> Result<()> {
+        self.settled_count = self.settled_count
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+        self.total_claimed = self.total_claimed
+            .checked_add(amount)
+            .ok_or(MarketError::TotalClaimedOverflow)?;
+        Ok(())
+    }
+}
+
+/// Per-mint collateral settings: ["collateral-config", mint]
+#[account]
+pub struct CollateralConfig {
+    pub mint: Pubkey,
+    pub min_bet: u64, // Mint base units
+    pub max_bet: u64, // Mint base units
+    pub max_market_size: u64, // Mint base units
+    pub max_position_per_wallet: u64, // Mint base units
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+/// Referral fees one referrer earned on one market: ["referral-fees", market, referrer]
+///
+/// Accrued per market so a cancelled market can still refund platform fees in full.
+#[account]
+pub struct ReferralFees {
+    pub market_id: u64,
+    pub referrer: Pubkey,
+    pub payer: Pubkey, // Fee payer who funded the account on the first referred bet
+    pub bets_referred: u64,
+    pub volume_referred: u64, // Gross amount of referred bets
+    pub accrued: u64,
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+impl ReferralFees {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // market_id
+        32 + // referrer
+        32 + // payer
+        8 * 4 + // bets_referred, volume_referred, accrued, claimed
+        1; // bump
+
+    /// Add a referred bet and its referral fee
+    pub fn record_bet(&mut self, amount: u64, referral_fee: u64) -> Result<()> {
+        self.bets_referred = self.bets_referred
+            .checked_add(1)
+            .ok_or(MarketError::TotalBetsOverflow)?;
+        self.volume_referred = self.volume_referred
+            .checked_add(amount)
+            .ok_or(MarketError::TotalVolumeOverflow)?;
+        self.accrued = self.accrued
+            .checked_add(referral_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        Ok(())
+    }
+
+    /// Take back (part of) a referred bet that was voided or sold (before any claim)
+    pub fn reverse_bet(&mut self, amount: u64, referral_fee: u64) -> Result<()> {
+        self.volume_referred = self.volume_referred.saturating_sub(amount);
+        self.accrued = self.accrued
+            .checked_sub(referral_fee)
+            .ok_or(MarketError::FeeOverflow)?;
+        Ok(())
+    }
+}
+
+/// Market metadata: ["market-metadata", market]
+///
+/// On-chain anchor for the off-chain metadata JSON (criteria, images, ...);
+/// indexers verify their copy against metadata_hash.
+#[account]
+pub struct MarketMetadata {
+    pub market_id: u64,
+    pub category: MarketCategory,
+    pub tags: Vec<String>,
+    pub resolution_source: String, // URI of the resolution source
+    pub metadata_hash: [u8; 32],   // Hash of the full off-chain metadata JSON
+    pub bump: u8,
+}
+
+impl MarketMetadata {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // market_id
+        1 + // category
+        4 + MAX_TAGS * (4 + MAX_TAG_LEN) + // tags
+        4 + MAX_RESOLUTION_SOURCE_LEN + // resolution_source
+        32 + // metadata_hash
+        1; // bump
+}
+
+/// create_market metadata argument (also used by proposal_system::create_proposal)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MarketMetadataArgs {
+    pub category: MarketCategory,
+    pub tags: Vec<String>,
+    pub resolution_source: String,
+    pub metadata_hash: [u8; 32],
+}
+
+impl MarketMetadataArgs {
+    /// Check tag and resolution source limits
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.tags.len() <= MAX_TAGS
+                && self
+                    .tags
+                    .iter()
+                    .all(|tag| !tag.is_empty() && tag.len() <= MAX_TAG_LEN),
+            MarketError::InvalidTags
+        );
+        require!(
+            self.resolution_source.len() <= MAX_RESOLUTION_SOURCE_LEN,
+            MarketError::InvalidResolutionSource
+        );
+        Ok(())
+    }
+}
+
+/// Parlay pool: ["parlay-pool", pool_id]
+///
+/// Parimutuel pool shared by every parlay on the same leg markets, with one
+/// stake bucket per Yes/No combination (bit i set = No on leg i).
+#[account]
+pub struct ParlayPool {
+    pub pool_id: [u8; 32],              // parlay_pool_id(legs)
+    pub legs: Vec<Pubkey>,              // Leg markets, ascending
+    pub combination_stakes: Vec<u64>,   // 2^legs buckets
+    pub total_stake: u64,
+    pub platform_fees: u64,             // Held until close_parlay_pool (refunded instead if refunds_all)
+    pub treasury_fees: u64,
+
+    // Settlement (first claim)
+    pub settled: bool,
+    pub winning_combination: u8,        // Resolved sides of the legs (No = bit set)
+    pub cancelled_legs: u8,             // Bit set = leg cancelled, dropped out
+    pub winning_stake: u64,             // Stake on combinations matching every settled leg
+
+    pub total_claimed: u64,
+    pub open_tickets: u64,              // Parlay tickets not yet claimed
+    pub payer: Pubkey,                  // Opened the pool, gets the rent back
+    pub created_at: i64,
+    pub settled_at: i64,
+    pub bump: u8,
+}
+
+impl ParlayPool {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // pool_id
+        4 + MAX_PARLAY_LEGS * 32 + // legs
+        4 + (1 << MAX_PARLAY_LEGS) * 8 + // combination_stakes
+        8 + // total_stake
+        8 * 2 + // platform_fees, treasury_fees
+        1 + // settled
+        2 + // winning_combination, cancelled_legs
+        8 + // winning_stake
+        8 * 2 + // total_claimed, open_tickets
+        32 + // payer
+        8 * 2 + // created_at, settled_at
+        1; // bump
+
+    /// Whether a ticket's combination agrees with every leg that did not cancel
+    pub fn is_winning(&self, combination: u8) -> bool {
+        (combination ^ self.winning_combination) & !self.cancelled_legs == 0
+    }
+
+    /// Record leg outcomes; every leg must be resolved or cancelled
+    ///
+    /// Releases each leg's open_dependents count (legs must be writable).
+    pub fn settle<'info>(&mut self, legs: &'info [AccountInfo<'info>], now: i64) -> Result<()> {
+        require!(legs.len() == self.legs.len(), MarketError::InvalidParlayLegs);
+
+        let mut winning_combination: u8 = 0;
+        let mut cancelled_legs: u8 = 0;
+        for (i, (leg, expected)) in legs.iter().zip(&self.legs).enumerate() {
+            require!(
+                leg.key() == *expected && leg.is_writable,
+                MarketError::InvalidParlayLegs
+            );
+            let mut market: Account<'info, Market> = Account::try_from(leg)?;
+            match market.status {
+                MarketStatus::Active => return err!(MarketError::ParlayLegsNotSettled),
+                MarketStatus::Cancelled => cancelled_legs |= 1 << i,
+                MarketStatus::Resolved => {
+                    if market.resolved_outcome == Some(BetSide::No) {
+                        winning_combination |= 1 << i;
+                    }
+                }
+            }
+            market.open_dependents = market.open_dependents.saturating_sub(1);
+            market.exit(&crate::ID)?;
+        }
+
+        self.winning_combination = winning_combination;
+        self.cancelled_legs = cancelled_legs;
+        let mut winning_stake: u64 = 0;
+        for (combination, stake) in self.combination_stakes.iter().enumerate() {
+            if self.is_winning(combination as u8) {
+                winning_stake = winning_stake
+                    .checked_add(*stake)
+                    .ok_or(MarketError::PoolOverflow)?;
+            }
+        }
+        self.winning_stake = winning_stake;
+        self.settled = true;
+        self.settled_at = now;
+        Ok(())
+    }
+
+    /// Whether a settled pool refunds every ticket in full (fees included):
+    /// every leg cancelled, or nobody holds the winning combination
+    pub fn refunds_all(&self) -> bool {
+        let all_legs = (1u8 << self.legs.len()) - 1;
+        self.winning_stake == 0 || self.cancelled_legs == all_legs
+    }
+
+    /// Payout for a ticket in a settled pool (its full amount back if refunds_all)
+    pub fn payout_for(&self, parlay: &Parlay) -> Result<u64> {
+        if self.refunds_all() {
+            return Ok(parlay.amount);
+        }
+        if !self.is_winning(parlay.combination) {
+            return Ok(0);
+        }
+        let payout = (parlay.stake as u128)
+            .checked_mul(self.total_stake as u128)
+            .ok_or(MarketError::PayoutCalculationOverflow)?
+            / self.winning_stake as u128;
+        Ok(payout as u64)
+    }
+}
+
+/// Parlay ticket: ["parlay", parlay_pool, bettor, nonce]
+#[account]
+pub struct Parlay {
+    pub pool: Pubkey,
+    pub bettor: Pubkey,
+    pub sides: Vec<BetSide>, // Side per leg, in pool order
+    pub combination: u8,     // Bit i set = No on leg i
+    pub amount: u64,         // Gross amount paid (refunded if the pool refunds_all)
+    pub stake: u64,          // amount minus platform and treasury fees
+    pub nonce: u64,          // Client-chosen, makes the ticket address unique
+    pub placed_at: i64,
+    pub bump: u8,
+}
+
+impl Parlay {
+    pub const LEN: usize = 8 + // discriminator
+        32 * 2 + // pool, bettor
+        4 + MAX_PARLAY_LEGS * 2 + // sides
+        1 + // combination
+        8 * 3 + // amount, stake, nonce
+        8 + // placed_at
+        1; // bump
+}
+
+/// Recurring market series: ["market-series", series_id]
+///
+/// Template for the markets created by spawn_next_market, plus series-wide
+/// stats. Holds the SOL that reimburses spawn rent.
+#[account]
+pub struct MarketSeries {
+    pub series_id: u32,
+    pub creator: Pubkey, // Creator of every child market (receives creator fees and rent)
+    pub template: SeriesTemplate,
+    pub active: bool,    // Paused series cannot spawn
+    pub first_end_date: i64,
+    pub last_end_date: i64, // End date of the latest child market
+
+    // Stats
+    pub markets_created: u32, // Also the index of the next child market
+    pub markets_settled: u32, // Resolved or cancelled child markets
+    pub total_volume: u64,    // Summed over settled child markets
+
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl MarketSeries {
+    pub const LEN: usize = 8 + // discriminator
+        4 + // series_id
+        32 + // creator
+        SeriesTemplate::LEN + // template
+        1 + // active
+        8 * 2 + // first_end_date, last_end_date
+        4 * 2 + // markets_created, markets_settled
+        8 + // total_volume
+        8 + // created_at
+        1; // bump
+
+    /// Deterministic id of the `index`-th child market of a series
+    pub fn market_id(series_id: u32, index: u32) -> u64 {
+        SERIES_MARKET_ID_FLAG | (series_id as u64) << 32 | index as u64
+    }
+
+    pub fn next_market_id(&self) -> u64 {
+        Self::market_id(self.series_id, self.markets_created)
+    }
+
+    /// End date of the next child market
+    ///
+    /// One duration after the previous end date (first_end_date for the first
+    /// market), moved forward by whole periods until at least `min_duration`
+    /// remains.
+    pub fn next_end_date(&self, now: i64, min_duration: i64) -> i64 {
+        let duration = self.template.duration_seconds;
+        let scheduled = if self.markets_created == 0 {
+            self.first_end_date
+        } else {
+            self.last_end_date.saturating_add(duration)
+        };
+
+        let shortfall = now.saturating_add(min_duration).saturating_sub(scheduled);
+        if shortfall <= 0 {
+            return scheduled;
+        }
+        let missed_periods = (shortfall + duration - 1) / duration;
+        scheduled.saturating_add(missed_periods.saturating_mul(duration))
+    }
+
+    /// Add a settled child market to the series stats
+    pub fn record_settlement(&mut self, market: &Market) -> Result<()> {
+        self.markets_settled = self
+            .markets_settled
+            .checked_add(1)
+            .ok_or(MarketError::SeriesOverflow)?;
+        self.total_volume = self
+            .total_volume
+            .checked_add(market.total_volume)
+            .ok_or(MarketError::TotalVolumeOverflow)?;
+        Ok(())
+    }
+}
+
+/// Template for the child markets of a MarketSeries
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SeriesTemplate {
+    pub title_pattern: String,     // "{n}" is replaced by the child market number
+    pub description: String,
+    pub duration_seconds: i64,     // Time between child market end dates
+    pub creator_fee_bps: u16,      // Replaces the bond-tier creator fee
+    pub resolver: Option<Pubkey>,  // May propose early resolution / report decision time
+    pub metadata: MarketMetadataArgs,
+}
+
+impl SeriesTemplate {
+    pub const LEN: usize = 4 + MAX_TITLE_LEN + // title_pattern
+        4 + MAX_DESCRIPTION_LEN + // description
+        8 + // duration_seconds
+        2 + // creator_fee_bps
+        33 + // resolver
+        1 + // metadata.category
+        4 + MAX_TAGS * (4 + MAX_TAG_LEN) + // metadata.tags
+        4 + MAX_RESOLUTION_SOURCE_LEN + // metadata.resolution_source
+        32; // metadata.metadata_hash
+
+    /// Check template limits against GlobalParameters
+    pub fn validate(&self, params: &GlobalParameters) -> Result<()> {
+        require!(
+            !self.title_pattern.is_empty() && self.title_pattern.len() <= MAX_TITLE_LEN,
+            MarketError::InvalidTitle
+        );
+        require!(
+            !self.description.is_empty() && self.description.len() <= MAX_DESCRIPTION_LEN,
+            MarketError::InvalidDescription
+        );
+        require!(self.duration_seconds > 0, MarketError::InvalidMarketDuration);
+        validate_market_duration(self.duration_seconds, params)?;
+        require!(
+            self.creator_fee_bps <= params.high_tier_fee_bps,
+            MarketError::InvalidSeriesFee
+        );
+        self.metadata.validate()
+    }
+}
+
+/// Protocol treasury: ["treasury"]
+///
+/// Holds SOL treasury fees directly; SPL treasury fees are paid to token
+/// accounts owned by this PDA.
+#[account]
+pub struct Treasury {
+    pub total_sol_collected: u64, // Lamports received from resolved SOL markets
+    pub total_sol_withdrawn: u64,
+    pub withdrawal_count: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + // discriminator
+        8 * 3 + // total_sol_collected, total_sol_withdrawn, withdrawal_count
+        8 + // created_at
+        1; // bump
+}
+
+/// Pending early resolution challenge: ["early-challenge", market]
+///
+/// Holds the challenger's bond (on top of rent) until the market is settled
+/// by vote and settle_early_resolution_challenge closes it.
+#[account]
+pub struct EarlyResolutionChallenge {
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    pub bond: u64, // Lamports, returned if the vote overturns the proposal, sent to the treasury otherwise
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl EarlyResolutionChallenge {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // market
+        32 + // challenger
+        8 + // bond
+        8 + // created_at
+        1; // bump
+}
+
+// ============================================================================
+// Enums
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MarketStatus {
+    Active,
+    Resolved,
+    Cancelled,
+}
+
+/// Handling of a resolution whose winning outcome has no stake
+/// (GlobalParameters.no_winner_policy)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum NoWinnerPolicy {
+    RefundBettors,   // 0: market becomes refundable via claim_refund
+    SweepToPlatform, // 1: pool transferred to the platform wallet
+}
+
+impl NoWinnerPolicy {
+    pub fn from_parameter(value: u8) -> Self {
+        match value {
+            1 => NoWinnerPolicy::SweepToPlatform,
+            _ => NoWinnerPolicy::RefundBettors,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MarketKind {
+    Binary,      // YES/NO pools
+    Categorical, // One pool per outcome label
+    Scalar {     // LONG/SHORT pools, linear payout between bounds
+        lower_bound: i64,
+        upper_bound: i64,
+    },
+}
+
+/// Market category (matches the frontend MARKET_CATEGORIES order)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MarketCategory {
+    Politics,
+    Sports,
+    Crypto,
+    Technology,
+    Entertainment,
+    Science,
+    Economics,
+    Other,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum PricingMode {
+    Parimutuel,                 // Pools split among winners at resolution
+    Lmsr { liquidity_b: u64 }, // Binary LMSR market maker, creator-funded liquidity b
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum BetSide {
+    Yes,
+    No,
+    Outcome(u8), // Categorical markets: index into outcome_labels
+    Long,        // Scalar markets: profits as the value approaches upper_bound
+    Short,       // Scalar markets: profits as the value approaches lower_bound
+}
+
+// Story 2.11: Bond tier enum from BondManager (for tiered creator fees)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum BondTier {
+    Tier1,  // Lowest bond, lowest creator fee (0.5%)
+    Tier2,  // Medium bond, medium creator fee (1.0%)
+    Tier3,  // Highest bond, highest creator fee (2.0%)
+}
+
+// Story 2.11: BondEscrow struct (minimal fields needed for reading bond_tier)
+#[account]
+pub struct BondEscrow {
+    pub creator: Pubkey,
+    pub bond_amount: u64,
+    pub market_id: u64,
+    pub status: u8, // Simplified: 0=Active, 1=Refunded, 2=PartialRefund, 3=Slashed
+    pub deposited_at: i64,
+    pub refunded_at: Option<i64>,
+    pub accumulated_fees: u64,
+    pub bond_tier: BondTier,
+    pub bump: u8,
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Market text limits (bytes)
+pub const MAX_TITLE_LEN: usize = 128;
+pub const MAX_DESCRIPTION_LEN: usize = 512;
+pub const MAX_OUTCOME_LABEL_LEN: usize = 32;
+
+/// Market metadata limits (tag count, bytes)
+pub const MAX_TAGS: usize = 5;
+pub const MAX_TAG_LEN: usize = 32;
+pub const MAX_RESOLUTION_SOURCE_LEN: usize = 200;
+
+/// Categorical outcome count limits
+pub const MIN_OUTCOMES: usize = 2;
+pub const MAX_OUTCOMES: usize = 16;
+
+/// Parlay leg count limits (combinations are tracked in a u8 bitmask)
+pub const MIN_PARLAY_LEGS: usize = 2;
+pub const MAX_PARLAY_LEGS: usize = 4;
+
+/// Market ids with the top bit set are reserved for series child markets;
+/// series ids must stay below MAX_SERIES_ID to keep clear of it
+pub const SERIES_MARKET_ID_FLAG: u64 = 1 << 63;
+pub const MAX_SERIES_ID: u32 = 1 << 31;
+
+/// Cap on the referrer share of the platform fee (basis points of the fee)
+pub const MAX_REFERRAL_FEE_BPS: u16 = 5000;
+
+/// claim_many batch layout: (market, user_bet, user_position) per bet
+pub const CLAIM_MANY_ACCOUNTS_PER_BET: usize = 3;
+pub const MAX_CLAIM_BATCH: usize = 20;
+
+/// Check that a claim_many triple belongs together and to the signing bettor
+///
+/// Account::try_from already checked program ownership and discriminators, and
+/// these accounts only exist at their PDAs, so matching ids and bettors is enough.
+fn validate_claim_batch_entry(
+    market: &Market,
+    user_bet: &UserBet,
+    user_position: &UserPosition,
+    bettor: &Pubkey,
+) -> Result<()> {
+    require!(
+        user_bet.market_id == market.market_id
+            && user_position.market_id == market.market_id,
+        MarketError::InvalidClaimBatch
+    );
+    require!(
+        user_bet.bettor == *bettor && user_position.bettor == *bettor,
+        MarketError::Unauthorized
+    );
+    Ok(())
+}
+
+/// Validate outcome labels and bounds against the requested market kind
+fn validate_market_kind(market_kind: &MarketKind, outcome_labels: &[String]) -> Result<()> {
+    match market_kind {
+        MarketKind::Binary => {
+            require!(outcome_labels.is_empty(), MarketError::InvalidOutcomeCount);
+        }
+        MarketKind::Scalar { lower_bound, upper_bound } => {
+            require!(outcome_labels.is_empty(), MarketError::InvalidOutcomeCount);
+            require!(lower_bound < upper_bound, MarketError::InvalidScalarBounds);
+        }
+        MarketKind::Categorical => {
+            require!(
+                outcome_labels.len() >= MIN_OUTCOMES && outcome_labels.len() <= MAX_OUTCOMES,
+                MarketError::InvalidOutcomeCount
+            );
+            require!(
+                outcome_labels
+                    .iter()
+                    .all(|label| !label.is_empty() && label.len() <= MAX_OUTCOME_LABEL_LEN),
+                MarketError::InvalidOutcomeLabel
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Merkle leaf for a pubkey in a market allowlist
+pub fn allowlist_leaf(member: &Pubkey) -> [u8; 32] {
+    solana_keccak_hasher::hash(member.as_ref()).to_bytes()
+}
+
+/// Verify a keccak256 Merkle proof of `leaf` against `root`
+///
+/// Pairs are hashed in sorted order (keccak256(min || max)), so proofs carry
+/// no left/right flags. Shared by market allowlists and
+/// market_resolution::VoteResult.merkle_root.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (first, second) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        solana_keccak_hasher::hashv(&[&first, &second]).to_bytes()
+    });
+    computed == *root
+}
+
+/// Settle a parlay pool from its legs and emit ParlayPoolSettledEvent
+///
+/// Shared by claim_parlay (first claim) and settle_parlay_pool.
+fn settle_parlay_pool_with_legs<'info>(
+    pool: &mut Account<'info, ParlayPool>,
+    legs: &'info [AccountInfo<'info>],
+    clock: &Clock,
+) -> Result<()> {
+    pool.settle(legs, clock.unix_timestamp)?;
+
+    emit!(ParlayPoolSettledEvent {
+        pool: pool.key(),
+        winning_combination: pool.winning_combination,
+        cancelled_legs: pool.cancelled_legs,
+        total_stake: pool.total_stake,
+        winning_stake: pool.winning_stake,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// ParlayPool id for a set of leg markets (ascending key order)
+pub fn parlay_pool_id(legs: &[Pubkey]) -> [u8; 32] {
+    let seeds: Vec<&[u8]> = legs.iter().map(|leg| leg.as_ref()).collect();
+    solana_sha256_hasher::hashv(&seeds).to_bytes()
+}
+
+/// Bet and size limits for a market: (min_bet, max_bet, max_market_size, max_position)
+///
+/// GlobalParameters for SOL, CollateralConfig for SPL mints; the market's
+/// overrides win over the size and position defaults. Shared by place_bet
+/// and seed_liquidity.
+fn bet_limits(
+    market: &Market,
+    collateral_config: Option<&Account<CollateralConfig>>,
+    params: &GlobalParameters,
+) -> Result<(u64, u64, u64, u64)> {
+    let (min_bet, max_bet, default_max_market_size, default_max_position) = match market.collateral_mint {
+        Some(mint) => {
+            let config = collateral_config.ok_or(MarketError::MissingCollateralAccounts)?;
+            require!(config.mint == mint, MarketError::InvalidCollateralAccount);
+            require!(config.enabled, MarketError::CollateralDisabled);
+            (
+                config.min_bet,
+                config.max_bet,
+                config.max_market_size,
+                config.max_position_per_wallet,
+            )
+        }
+        None => (
+            params.min_bet_lamports,
+            params.max_bet_lamports,
+            params.max_market_size_lamports,
+            params.max_position_per_wallet_lamports,
+        ),
+    };
+    Ok((
+        min_bet,
+        max_bet,
+        market.max_market_size_override.unwrap_or(default_max_market_size),
+        market.max_position_per_wallet_override.unwrap_or(default_max_position),
+    ))
+}
+
+/// Check a market duration against the GlobalParameters window
+fn validate_market_duration(duration: i64, params: &GlobalParameters) -> Result<()> {
+    if duration < params.min_duration_seconds || duration > params.max_duration_seconds {
+        msg!(
+            "Market duration {}s outside allowed window {}s-{}s",
+            duration,
+            params.min_duration_seconds,
+            params.max_duration_seconds
+        );
+        return err!(MarketError::InvalidMarketDuration);
+    }
+    Ok(())
+}
+
+/// Initialize a new market account (create_market, spawn_next_market)
+///
+/// Sets everything but collateral, which starts as native SOL.
+fn init_market(
+    market: &mut Market,
+    market_id: u64,
+    creator: Pubkey,
+    title: String,
+    description: String,
+    end_date: i64,
+    market_kind: MarketKind,
+    outcome_labels: Vec<String>,
+    pricing_mode: PricingMode,
+    params: &GlobalParameters,
+    clock: &Clock,
+    bump: u8,
+) {
+    market.market_id = market_id;
+    market.creator = creator;
+    market.title = title;
+    market.description = description;
+    market.end_date = end_date;
+    market.betting_closes_at = end_date
+        .saturating_sub(params.betting_cutoff_seconds)
+        .max(clock.unix_timestamp);
+
+    // Initial pools (empty)
+    market.yes_pool = 0;
+    market.no_pool = 0;
+    market.total_volume = 0;
+    market.creator_seed = 0;
+
+    // Categorical outcomes (empty for binary markets)
+    market.market_kind = market_kind;
+    market.outcome_pools = vec![0; outcome_labels.len()];
+    market.outcome_labels = outcome_labels;
+    market.resolved_value = None;
+
+    // Collateral (None = native SOL)
+    market.collateral_mint = None;
+    market.collateral_vault = None;
+    market.max_market_size_override = None;
+    market.max_position_per_wallet_override = None;
+
+    // Series (standalone until spawn_next_market sets them)
+    market.series = None;
+    market.creator_fee_bps_override = None;
+    market.resolver = None;
+
+    // Condition (unconditional unless create_market sets a parent)
+    market.parent_market = None;
+    market.required_outcome = None;
+    market.condition_met = false;
+    market.condition_settled = false;
+
+    // Allowlist (open unless create_market sets one)
+    market.allowlist_root = None;
+
+    // Pricing (LMSR share counters start at zero)
+    market.pricing_mode = pricing_mode;
+    market.yes_shares = 0;
+    market.no_shares = 0;
+    market.lmsr_subsidy = 0;
+    market.lmsr_surplus_claimed = false;
+    market.forfeited_stake = 0;
+
+    // Fee tracking (initially zero)
+    market.total_platform_fees = 0;
+    market.total_creator_fees = 0;
+    market.total_treasury_fees = 0;
+    market.total_referral_fees = 0;
+
+    // Payout tracking (initially zero)
+    market.total_claimed = 0;
+
+    // Retroactive void (none until a decision time is reported)
+    market.voided_after = None;
+    market.void_from_bet = 0;
+    market.voided_bets = 0;
+    market.voided_stake = 0;
+
+    // Early resolution (none proposed)
+    market.halted_at = None;
+    market.early_outcome = None;
+    market.early_challenged = false;
+
+    // Status
+    market.status = MarketStatus::Active;
+    market.created_at = clock.unix_timestamp;
+    market.settled_at = 0;
+    market.resolved_outcome = None;
+
+    // Tracking
+    market.total_bets = 0;
+    market.unique_bettors = 0;
+    market.open_child_accounts = 0;
+    market.open_dependents = 0;
+    market.bump = bump;
+}
+
+/// Validate the pricing mode against the market kind
+fn validate_pricing_mode(market_kind: &MarketKind, pricing_mode: &PricingMode) -> Result<()> {
+    if let PricingMode::Lmsr { liquidity_b } = pricing_mode {
+        require!(
+            *market_kind == MarketKind::Binary,
+            MarketError::LmsrRequiresBinaryMarket
+        );
+        require!(*liquidity_b > 0, MarketError::InvalidLiquidityParameter);
+    }
+    Ok(())
+}
+
+/// Calculate per-outcome odds in basis points for a categorical market
+fn calculate_categorical_odds(pools: &[u64]) -> Vec<u16> {
+    let total: u128 = pools.iter().map(|pool| *pool as u128).sum();
+    if total == 0 {
+        // Uniform odds if no bets yet
+        return vec![(10000 / pools.len()) as u16; pools.len()];
+    }
+    pools
+        .iter()
+        .map(|pool| ((*pool as u128 * 10000) / total) as u16)
+        .collect()
+}
+
+/// Calculate YES odds in basis points
+fn calculate_odds(yes_pool: u64, no_pool: u64) -> u16 {
+    let total = yes_pool + no_pool;
+    if total == 0 {
+        return 5000; // 50% if no bets yet
+    }
+    ((yes_pool as u128 * 10000) / total as u128) as u16
+}
+
+/// Story 2.11: Get creator fee percentage based on bond tier
+/// Maps bond tier to corresponding fee percentage from ParameterStorage
+fn get_creator_fee_bps_for_tier(bond_tier: BondTier, params: &GlobalParameters) -> u16 {
+    match bond_tier {
+        BondTier::Tier1 => params.low_tier_fee_bps,
+        BondTier::Tier2 => params.medium_tier_fee_bps,
+        BondTier::Tier3 => params.high_tier_fee_bps,
+    }
+}
+
+/// Token accounts backing an SPL-denominated market
+struct CollateralAccounts<'a, 'info> {
+    mint: &'a InterfaceAccount<'info, Mint>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Validate the optional collateral accounts against the market
+///
+/// Returns None for native SOL markets (any passed accounts are ignored).
+fn load_collateral<'a, 'info>(
+    market: &Market,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    vault: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+) -> Result<Option<CollateralAccounts<'a, 'info>>> {
+    let Some(expected_mint) = market.collateral_mint else {
+        return Ok(None);
+    };
+
+    let (Some(mint), Some(vault), Some(token_program)) = (mint, vault, token_program) else {
+        return err!(MarketError::MissingCollateralAccounts);
+    };
+    require!(
+        mint.key() == expected_mint
+            && Some(vault.key()) == market.collateral_vault
+            && *mint.to_account_info().owner == token_program.key(),
+        MarketError::InvalidCollateralAccount
+    );
+
+    Ok(Some(CollateralAccounts {
+        mint,
+        vault,
+        token_program,
+    }))
+}
+
+/// Move collateral from a user into the market
+///
+/// Returns the amount actually received, which is lower than `amount`
+/// for Token-2022 mints with a transfer fee.
+fn deposit_collateral<'info>(
+    market: &Account<'info, Market>,
+    collateral: Option<&CollateralAccounts<'_, 'info>>,
+    depositor: &Signer<'info>,
+    depositor_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<u64> {
+    let Some(collateral) = collateral else {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: depositor.to_account_info(),
+                    to: market.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        return Ok(amount);
+    };
+
+    let from = depositor_token_account.ok_or(MarketError::MissingCollateralAccounts)?;
+    require!(
+        from.mint == collateral.mint.key() && from.owner == depositor.key(),
+        MarketError::InvalidCollateralAccount
+    );
+
+    let balance_before = collateral.vault.amount;
+    transfer_checked(
+        CpiContext::new(
+            collateral.token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: collateral.mint.to_account_info(),
+                to: collateral.vault.to_account_info(),
+                authority: depositor.to_account_info(),
+            },
+        ),
+        amount,
+        collateral.mint.decimals,
+    )?;
+
+    // Re-read the vault: transfer fees are withheld from the destination
+    let vault_info = collateral.vault.to_account_info();
+    let vault_data = vault_info.try_borrow_data()?;
+    let balance_after = TokenAccount::try_deserialize(&mut &vault_data[..])?.amount;
+
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(MarketError::PoolOverflow)?;
+    require!(received > 0, MarketError::BetTooSmall);
+    Ok(received)
+}
+
+/// Move collateral out of the market to a recipient wallet
+///
+/// SOL markets debit the market PDA's lamports directly; SPL markets
+/// transfer from the vault to the recipient's token account, signed by the market PDA.
+fn withdraw_collateral<'info>(
+    market: &Account<'info, Market>,
+    collateral: Option<&CollateralAccounts<'_, 'info>>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let Some(collateral) = collateral else {
+        **market.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **recipient.try_borrow_mut_lamports()? += amount;
+        return Ok(());
+    };
+
+    let to = recipient_token_account.ok_or(MarketError::MissingCollateralAccounts)?;
+    require!(
+        to.mint == collateral.mint.key() && to.owner == recipient.key(),
+        MarketError::InvalidCollateralAccount
+    );
+
+    let market_id_bytes = market.market_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"market", market_id_bytes.as_ref(), &[market.bump]]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            collateral.token_program.to_account_info(),
+            TransferChecked {
+                from: collateral.vault.to_account_info(),
+                mint: collateral.mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        collateral.mint.decimals,
+    )
+}
+
+/// Reject when the emergency pause is set or the given feature toggle is off
+fn require_feature_enabled(
+    global_toggles: &AccountInfo,
+    is_enabled: fn(&GlobalFeatureToggles) -> bool,
+    disabled_error: MarketError,
+) -> Result<()> {
+    let parameter_storage_program = Pubkey::from_str(PARAMETER_STORAGE_PROGRAM_ID_STR)
+        .map_err(|_| MarketError::InvalidFeatureToggles)?;
+    require!(
+        *global_toggles.owner == parameter_storage_program,
+        MarketError::InvalidFeatureToggles
+    );
+
+    let toggles_data = global_toggles.try_borrow_data()?;
+    let toggles = GlobalFeatureToggles::try_deserialize(&mut &toggles_data[..])?;
+
+    require!(!toggles.emergency_pause, MarketError::EmergencyPaused);
+    if !is_enabled(&toggles) {
+        return Err(disabled_error.into());
+    }
+    Ok(())
+}
+
+/// MarketResolution PDA allowed to resolve and cancel markets
+fn resolver_authority() -> Result<Pubkey> {
+    let market_resolution_program = Pubkey::from_str(MARKET_RESOLUTION_PROGRAM_ID_STR)
+        .map_err(|_| MarketError::Unauthorized)?;
+    let (resolver, _) =
+        Pubkey::find_program_address(&[RESOLVER_AUTHORITY_SEED], &market_resolution_program);
+    Ok(resolver)
+}
+
+/// Shared resolution checks for resolve_market and resolve_scalar_market
+fn validate_resolution(accounts: &ResolveMarket, clock: &Clock) -> Result<()> {
+    // PROTECTION 1: Only the MarketResolution resolver PDA can resolve (authorization)
+    require!(
+        accounts.authority.key() == resolver_authority()?,
+        MarketError::Unauthorized
+    );
+
+    // An unchallenged early proposal is settled by finalize_early_resolution;
+    // a challenged one falls back to this (vote) path
+    require!(
+        !accounts.market.early_resolution_pending(),
+        MarketError::EarlyResolutionPending
+    );
+
+    // PROTECTIONS 2-6: platform wallet, status, end date, voided bets, condition
+    validate_settlement(accounts, accounts.market.end_date, clock, MarketError::MarketNotEnded)
+}
+
+/// Settlement checks shared by resolver and early resolution paths
+///
+/// `settles_from` is the earliest settlement time: end_date for the resolver,
+/// the end of the challenge period for finalize_early_resolution.
+fn validate_settlement(
+    accounts: &ResolveMarket,
+    settles_from: i64,
+    clock: &Clock,
+    too_early: MarketError,
+) -> Result<()> {
+    require_feature_enabled(
+        &accounts.global_toggles,
+        |toggles| toggles.resolution_enabled,
+        MarketError::ResolutionDisabled,
+    )?;
+
+    // Get global parameters for platform wallet validation
+    let global_params_data = accounts.global_parameters.try_borrow_data()?;
+    let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+    let market = &accounts.market;
+
+    // PROTECTION 2: Validate platform wallet matches global parameters
+    require!(
+        accounts.platform_wallet.key() == params.authority,
+        MarketError::Unauthorized
+    );
+
+    // PROTECTION 3: Market must be active
+    require!(
+        market.status == MarketStatus::Active,
+        MarketError::MarketAlreadyResolved
+    );
+
+    // PROTECTION 4: Cannot settle before the end date (or challenge period)
+    if clock.unix_timestamp < settles_from {
+        return Err(too_early.into());
+    }
+
+    // PROTECTION 5: Bets after a reported decision time must all be voided first
+    require!(market.pending_voids() == 0, MarketError::VoidedBetsPending);
+
+    // PROTECTION 6: Conditional markets wait for the parent's required outcome
+    require!(
+        market.parent_market.is_none() || market.condition_met,
+        MarketError::ConditionNotSettled
+    );
+
+    Ok(())
+}
+
+/// Pay accumulated platform, creator and treasury fees out of the market
+///
+/// The referrers' share of platform fees stays in the market for claim_referral_fees.
+/// Returns (platform_fees, creator_fees, treasury_fees) for logging.
+fn distribute_fees(accounts: &mut ResolveMarket) -> Result<(u64, u64, u64)> {
+    let platform_fees = accounts.market.total_platform_fees
+        .saturating_sub(accounts.market.total_referral_fees);
+    let creator_fees = accounts.market.total_creator_fees;
+    let treasury_fees = accounts.market.total_treasury_fees;
+
+    let collateral = load_collateral(
+        &accounts.market,
+        &accounts.collateral_mint,
+        &accounts.market_vault,
+        &accounts.token_program,
+    )?;
+
+    withdraw_collateral(
+        &accounts.market,
+        collateral.as_ref(),
+        &accounts.platform_wallet,
+        accounts.platform_token_account.as_ref(),
+        platform_fees,
+    )?;
+
+    withdraw_collateral(
+        &accounts.market,
+        collateral.as_ref(),
+        &accounts.creator_wallet,
+        accounts.creator_token_account.as_ref(),
+        creator_fees,
+    )?;
+
+    withdraw_collateral(
+        &accounts.market,
+        collateral.as_ref(),
+        &accounts.treasury.to_account_info(),
+        accounts.treasury_token_account.as_ref(),
+        treasury_fees,
+    )?;
+
+    if accounts.market.collateral_mint.is_none() {
+        accounts.treasury.total_sol_collected = accounts.treasury.total_sol_collected
+            .checked_add(treasury_fees)
+            .ok_or(MarketError::FeeOverflow)?;
+    }
+
+    Ok((platform_fees, creator_fees, treasury_fees))
+}
+
+/// Resolve a validated binary/categorical market to `outcome`
+///
+/// Shared by resolve_market and finalize_early_resolution.
+fn resolve_with_outcome(
+    accounts: &mut ResolveMarket,
+    outcome: BetSide,
+    clock: &Clock,
+) -> Result<()> {
+    let market = &mut accounts.market;
+
+    // PROTECTION 5: Outcome must exist for this market kind
+    require!(!market.is_scalar(), MarketError::ScalarMarketRequiresValue);
+    market.validate_bet_side(&outcome)?;
+
+    // Nobody backed the winning outcome: apply GlobalParameters.no_winner_policy
+    // (LMSR winners are paid from shares; an unbacked outcome leaves the pool
+    // to the creator via claim_lmsr_surplus)
+    if !market.is_lmsr() && market.pool_for(&outcome) == 0 && market.total_pool()? > 0 {
+        return resolve_without_winners(accounts, outcome, clock);
+    }
+
+    // Update market status and outcome
+    market.status = MarketStatus::Resolved;
+    market.settled_at = clock.unix_timestamp;
+    market.resolved_outcome = Some(outcome.clone());
+
+    // Copy values for event before transfers
+    let market_id = market.market_id;
+    let yes_pool = market.yes_pool;
+    let no_pool = market.no_pool;
+
+    // Distribute accumulated fees
+    let (platform_fees, creator_fees, treasury_fees) = distribute_fees(accounts)?;
+
+    let outcome_copy = outcome.clone();
+
+    emit!(MarketResolvedEvent {
+        market_id,
+        outcome,
+        yes_pool,
+        no_pool,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Market {} resolved: {:?} | Platform fees: {} | Creator fees: {} | Treasury fees: {}",
+        market_id,
+        outcome_copy,
+        platform_fees,
+        creator_fees,
+        treasury_fees
+    );
+
+    Ok(())
+}
+
+/// Count a settled market in its series stats
+///
+/// Series markets must pass their MarketSeries; standalone markets pass none.
+fn record_series_settlement(
+    series: &mut Option<Account<MarketSeries>>,
+    market: &Market,
+) -> Result<()> {
+    match (market.series, series.as_mut()) {
+        (None, None) => Ok(()),
+        (Some(expected), Some(series)) if series.key() == expected => {
+            series.record_settlement(market)
+        }
+        _ => err!(MarketError::InvalidSeriesAccount),
+    }
+}
+
+/// Move an active market to Cancelled; bettors then claim full refunds
+fn mark_cancelled(market: &mut Market, clock: &Clock) {
+    market.status = MarketStatus::Cancelled;
+    market.settled_at = clock.unix_timestamp;
+
+    emit!(MarketCancelledEvent {
+        market_id: market.market_id,
+        yes_pool: market.yes_pool,
+        no_pool: market.no_pool,
+        total_bets: market.total_bets,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Market {} cancelled: {} bets, {} SOL yes pool, {} SOL no pool",
+        market.market_id,
+        market.total_bets,
+        market.yes_pool as f64 / 1_000_000_000.0,
+        market.no_pool as f64 / 1_000_000_000.0
+    );
+}
+
+/// Resolve a parimutuel market whose winning outcome has no stake
+///
+/// For scalar markets `outcome` is the side a bound value pays in full.
+///
+/// REFUND_BETTORS moves the market to Cancelled so every bettor recovers their
+/// full bet (fees included) via claim_refund or claim_many; fees are never
+/// distributed. SWEEP_TO_PLATFORM resolves the market, distributes fees and
+/// transfers the unclaimable pool to the platform wallet.
+fn resolve_without_winners(
+    accounts: &mut ResolveMarket,
+    outcome: BetSide,
+    clock: &Clock,
+) -> Result<()> {
+    let policy = {
+        let global_params_data = accounts.global_parameters.try_borrow_data()?;
+        let params = GlobalParameters::try_deserialize(&mut &global_params_data[..])?;
+        NoWinnerPolicy::from_parameter(params.no_winner_policy)
+    };
+
+    let total_pool = accounts.market.total_pool()?;
+    accounts.market.resolved_outcome = Some(outcome.clone());
+    accounts.market.settled_at = clock.unix_timestamp;
+
+    let (platform_fees, creator_fees, treasury_fees, swept_amount) = match policy {
+        NoWinnerPolicy::RefundBettors => {
+            accounts.market.status = MarketStatus::Cancelled;
+            (0, 0, 0, 0)
+        }
+        NoWinnerPolicy::SweepToPlatform => {
+            accounts.market.status = MarketStatus::Resolved;
+
+            // Nothing is claimable: count the whole pool as paid out
+            accounts.market.total_claimed = accounts.market.total_claimed
+                .checked_add(total_pool)
+                .ok_or(MarketError::TotalClaimedOverflow)?;
+
+            let (platform_fees, creator_fees, treasury_fees) = distribute_fees(accounts)?;
+
+            let collateral = load_collateral(
+                &accounts.market,
+                &accounts.collateral_mint,
+                &accounts.market_vault,
+                &accounts.token_program,
+            )?;
+            withdraw_collateral(
+                &accounts.market,
+                collateral.as_ref(),
+                &accounts.platform_wallet,
+                accounts.platform_token_account.as_ref(),
+                total_pool,
+            )?;
+
+            (platform_fees, creator_fees, treasury_fees, total_pool)
+        }
+    };
+
+    let market_id = accounts.market.market_id;
+
+    emit!(NoWinnerResolutionEvent {
+        market_id,
+        outcome: outcome.clone(),
+        policy: policy.clone(),
+        total_pool,
+        swept_amount,
+        platform_fees,
+        creator_fees,
+        treasury_fees,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Market {} resolved {:?} with no winning stake | Policy: {:?} | Pool: {} | Swept: {}",
+        market_id,
+        outcome,
+        policy,
+        total_pool,
+        swept_amount
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// LMSR Math (fixed point)
+// ============================================================================
+
+/// Fixed-point scale for LMSR math (1e12)
+const LMSR_SCALE: i128 = 1_000_000_000_000;
+
+/// ln(2) at LMSR_SCALE
+const LMSR_LN2: i128 = 693_147_180_560;
+
+/// Largest |exponent| the LMSR math accepts (keeps e^x well inside i128)
+const LMSR_MAX_EXPONENT: i128 = 20 * LMSR_SCALE;
+
+/// e^x for fixed-point x, |x| <= LMSR_MAX_EXPONENT
+fn fp_exp(x: i128) -> Option<i128> {
+    if x.abs() > LMSR_MAX_EXPONENT {
+        return None;
+    }
+
+    // Range reduction: x = k * ln2 + r, |r| <= ln2 / 2
+    let k = if x >= 0 {
+        (x + LMSR_LN2 / 2) / LMSR_LN2
+    } else {
+        (x - LMSR_LN2 / 2) / LMSR_LN2
+    };
+    let r = x - k * LMSR_LN2;
+
+    // Taylor series for e^r
+    let mut term = LMSR_SCALE;
+    let mut sum = LMSR_SCALE;
+    let mut n = 1;
+    while term != 0 && n < 30 {
+        term = term * r / (n * LMSR_SCALE);
+        sum += term;
+        n += 1;
+    }
+
+    Some(if k >= 0 { sum << k } else { sum >> -k })
+}
+
+/// e^x for fixed-point x <= 0, flushing to zero below -LMSR_MAX_EXPONENT
+fn fp_exp_neg(x: i128) -> i128 {
+    fp_exp(x).unwrap_or(0)
+}
+
+/// ln(x) for fixed-point x > 0
+fn fp_ln(x: i128) -> Option<i128> {
+    if x <= 0 {
+        return None;
+    }
+
+    // Normalize x = m * 2^k with m in [1, 2)
+    let mut m = x;
+    let mut k: i128 = 0;
+    while m >= 2 * LMSR_SCALE {
+        m >>= 1;
+        k += 1;
+    }
+    while m < LMSR_SCALE {
+        m <<= 1;
+        k -= 1;
+    }
+
+    // ln(m) = 2 * atanh(z), z = (m - 1) / (m + 1) in [0, 1/3)
+    let z = (m - LMSR_SCALE) * LMSR_SCALE / (m + LMSR_SCALE);
+    let z_squared = z * z / LMSR_SCALE;
+    let mut power = z;
+    let mut sum = 0;
+    let mut n = 1;
+    while power != 0 && n < 60 {
+        sum += power / n;
+        power = power * z_squared / LMSR_SCALE;
+        n += 2;
+    }
+
+    Some(k * LMSR_LN2 + 2 * sum)
+}
+
+/// Share imbalance (other - side) / b at LMSR_SCALE
+fn lmsr_imbalance(side_shares: u64, other_shares: u64, liquidity_b: u64) -> i128 {
+    (other_shares as i128 - side_shares as i128) * LMSR_SCALE / liquidity_b as i128
+}
+
+/// Maximum creator loss for a binary LMSR market: b * ln(2), rounded up
+fn lmsr_max_loss(liquidity_b: u64) -> Option<u64> {
+    let loss = (liquidity_b as i128).checked_mul(LMSR_LN2)? / LMSR_SCALE + 1;
+    u64::try_from(loss).ok()
+}
+
+/// Instantaneous price of a side in basis points: 1 / (1 + e^((other - side) / b))
+fn lmsr_price_bps(side_shares: u64, other_shares: u64, liquidity_b: u64) -> u16 {
+    let d = lmsr_imbalance(side_shares, other_shares, liquidity_b);
+    let price = if d <= 0 {
+        10000 * LMSR_SCALE / (LMSR_SCALE + fp_exp_neg(d))
+    } else {
+        let e_neg_d = fp_exp_neg(-d);
+        10000 * e_neg_d / (e_neg_d + LMSR_SCALE)
+    };
+    price as u16
+}
+
+/// Shares of a side bought for `cost`, rounded down (in the market's favour)
+///
+/// Inverts C(q + shares) - C(q) = cost for the binary cost function
+/// C(q) = b * ln(e^(q_yes / b) + e^(q_no / b)). Returns None if the trade
+/// would push prices past the representable range.
+fn lmsr_shares_for_cost(
+    side_shares: u64,
+    other_shares: u64,
+    liquidity_b: u64,
+    cost: u64,
+) -> Option<u64> {
+    let b = liquidity_b as i128;
+    let d = lmsr_imbalance(side_shares, other_shares, liquidity_b);
+    let e_c = fp_exp((cost as i128).checked_mul(LMSR_SCALE)? / b)?;
+
+    // shares / b = ln(e^(c/b) * (1 + e^d) - e^d), factoring out e^d when d > 0
+    let scaled_shares = if d <= 0 {
+        let e_d = fp_exp_neg(d);
+        let inner = e_c.checked_mul(LMSR_SCALE + e_d)? / LMSR_SCALE - e_d;
+        fp_ln(inner)?
+    } else {
+        let e_neg_d = fp_exp_neg(-d);
+        let inner = e_c.checked_mul(LMSR_SCALE + e_neg_d)? / LMSR_SCALE - LMSR_SCALE;
+        d + fp_ln(inner)?
+    };
+
+    let shares = scaled_shares.checked_mul(b)? / LMSR_SCALE;
+    if shares <= 0 {
+        return Some(0);
+    }
+
+    // Reject trades that push the imbalance past LMSR_MAX_EXPONENT
+    let new_side_shares = (side_shares as i128).checked_add(shares)?;
+    if (other_shares as i128 - new_side_shares).abs() * LMSR_SCALE / b > LMSR_MAX_EXPONENT {
+        return None;
+    }
+    u64::try_from(shares).ok()
+}
+
+/// Collateral returned for selling `shares` of a side, rounded down (in the market's favour)
+///
+/// C(q) - C(q - shares) = b * ln((1 + e^d) / (e^(-shares / b) + e^d)),
+/// factoring out e^d when d > 0 so every exponent is non-positive.
+fn lmsr_proceeds_for_shares(
+    side_shares: u64,
+    other_shares: u64,
+    liquidity_b: u64,
+    shares: u64,
+) -> Option<u64> {
+    if shares > side_shares {
+        return None;
+    }
+    let b = liquidity_b as i128;
+    let d = lmsr_imbalance(side_shares, other_shares, liquidity_b);
+    let delta = (shares as i128) * LMSR_SCALE / b;
+
+    let scaled_proceeds = if d <= 0 {
+        let e_d = fp_exp_neg(d);
+        fp_ln(LMSR_SCALE + e_d)? - fp_ln(fp_exp_neg(-delta) + e_d)?
+    } else {
+        let e_neg_d = fp_exp_neg(-d);
+        fp_ln(e_neg_d + LMSR_SCALE)? - fp_ln(fp_exp_neg(-delta - d) + LMSR_SCALE)?
+    };
+
+    // One unit of slack absorbs fixed-point rounding
+    let proceeds = scaled_proceeds.checked_mul(b)? / LMSR_SCALE - 1;
+    u64::try_from(proceeds.max(0)).ok()
+}
+
+// ============================================================================
+// Instruction Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(
+    market_id: u64,
+    title: String,
+    description: String,
+    end_date: i64,
+    market_kind: MarketKind,
+    outcome_labels: Vec<String>
+)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = Market::space(outcome_labels.len()), // ~800 bytes binary, ~1.4KB at 16 outcomes
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = MarketMetadata::LEN,
+        seeds = [b"market-metadata", market.key().as_ref()],
+        bump
+    )]
+    pub market_metadata: Account<'info, MarketMetadata>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// SPL collateral mint (omit for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Per-mint collateral settings (required with collateral_mint)
+    pub collateral_config: Option<Account<'info, CollateralConfig>>,
+
+    /// Market-owned token vault (required with collateral_mint)
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market-vault", market.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = market,
+        token::token_program = token_program
+    )]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Creator's token account (funds the LMSR subsidy on SPL markets)
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Parent of a conditional market (omit for unconditional markets)
+    #[account(
+        mut,
+        seeds = [b"market", parent_market.market_id.to_le_bytes().as_ref()],
+        bump = parent_market.bump
+    )]
+    pub parent_market: Option<Account<'info, Market>>,
+}
+
+#[derive(Accounts)]
+#[instruction(series_id: u32)]
+pub struct CreateSeries<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = MarketSeries::LEN,
+        seeds = [b"market-series", series_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub series: Account<'info, MarketSeries>,
+
+    /// Platform authority (creator of every child market)
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSeries<'info> {
+    #[account(
+        mut,
+        seeds = [b"market-series", series.series_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = creator @ MarketError::Unauthorized
+    )]
+    pub series: Account<'info, MarketSeries>,
+
+    pub creator: Signer<'info>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpawnNextMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market-series", series.series_id.to_le_bytes().as_ref()],
+        bump = series.bump
+    )]
+    pub series: Account<'info, MarketSeries>,
+
+    #[account(
+        init,
+        payer = cranker,
+        space = Market::space(0),
+        seeds = [b"market", series.next_market_id().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = cranker,
+        space = MarketMetadata::LEN,
+        seeds = [b"market-metadata", market.key().as_ref()],
+        bump
+    )]
+    pub market_metadata: Account<'info, MarketMetadata>,
+
+    /// Anyone; rent is reimbursed from the series balance
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSeries<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"market-series", series.series_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = creator @ MarketError::Unauthorized
+    )]
+    pub series: Account<'info, MarketSeries>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init_if_needed,
+        payer = fee_payer,
+        space = UserPosition::space(market.outcome_count()),
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(
+        init,
+        payer = fee_payer,
+        space = UserBet::LEN, // ~160 bytes
+        seeds = [
+            b"user-bet",
+            market.key().as_ref(),
+            bettor.key().as_ref(),
+            &user_position.bet_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    /// CHECK: Bond escrow from BondManager program - validated via seeds
+    /// Story 2.11: Read bond tier for tiered creator fees (unused, and may be
+    /// uninitialized, for series markets)
+    #[account(
+        seeds = [b"bond-escrow", market.market_id.to_le_bytes().as_ref()],
+        bump,
+        seeds::program = bond_manager_program.key()
+    )]
+    pub bond_escrow: AccountInfo<'info>,
+
+    /// Source of the stake (SOL markets)
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    /// Pays account rent: a relayer for gasless bets, otherwise the bettor
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// CHECK: BondManager program ID
+    pub bond_manager_program: AccountInfo<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub collateral_config: Option<Account<'info, CollateralConfig>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bettor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Referrer wallet credited with part of the platform fee (omit with referral_fees)
+    #[account(constraint = referrer.key() != bettor.key() @ MarketError::SelfReferral)]
+    pub referrer: Option<AccountInfo<'info>>,
+
+    #[account(
+        init_if_needed,
+        payer = fee_payer,
+        space = ReferralFees::LEN,
+        seeds = [
+            b"referral-fees",
+            market.key().as_ref(),
+            referrer.as_ref().map(|referrer| referrer.key()).unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub referral_fees: Option<Account<'info, ReferralFees>>,
+
+    /// Parent of a conditional market (required until its condition is settled)
+    #[account(
+        seeds = [b"market", parent_market.market_id.to_le_bytes().as_ref()],
+        bump = parent_market.bump
+    )]
+    pub parent_market: Option<Account<'info, Market>>,
+}
+
+#[derive(Accounts)]
+pub struct SeedLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        has_one = creator @ MarketError::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = UserPosition::space(market.outcome_count()),
+        seeds = [b"user-position", market.key().as_ref(), creator.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = UserBet::LEN,
+        seeds = [b"user-bet", market.key().as_ref(), creator.key().as_ref(), &0u64.to_le_bytes()],
+        bump
+    )]
+    pub yes_bet: Account<'info, UserBet>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = UserBet::LEN,
+        seeds = [b"user-bet", market.key().as_ref(), creator.key().as_ref(), &1u64.to_le_bytes()],
+        bump
+    )]
+    pub no_bet: Account<'info, UserBet>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Per-mint collateral settings (required with collateral_mint)
+    pub collateral_config: Option<Account<'info, CollateralConfig>>,
+
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct SellPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"user-bet",
+            market.key().as_ref(),
+            bettor.key().as_ref(),
+            &user_bet.bet_index.to_le_bytes()
+        ],
+        bump = user_bet.bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Referrer's accrual for this market (referred bets only)
+    #[account(
+        mut,
+        constraint = referral_fees.market_id == market.market_id
+            && Some(referral_fees.referrer) == user_bet.referrer
+            @ MarketError::MissingReferralAccounts
+    )]
+    pub referral_fees: Option<Account<'info, ReferralFees>>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bettor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ReportDecisionTime<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// First bet placed at or after the decision time (omit if none)
+    pub first_late_bet: Option<Account<'info, UserBet>>,
+
+    /// Bet placed just before first_late_bet (omit if every bet is late)
+    pub last_early_bet: Option<Account<'info, UserBet>>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// Platform authority or MarketResolution resolver PDA
+    pub authority: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VoidBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"user-bet",
+            market.key().as_ref(),
+            user_bet.bettor.as_ref(),
+            &user_bet.bet_index.to_le_bytes()
+        ],
+        bump = user_bet.bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), user_bet.bettor.as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Referrer's accrual for this market (referred bets only)
+    #[account(
+        mut,
+        constraint = referral_fees.market_id == market.market_id
+            && Some(referral_fees.referrer) == user_bet.referrer
+            @ MarketError::MissingReferralAccounts
+    )]
+    pub referral_fees: Option<Account<'info, ReferralFees>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeEarlyResolution<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// Platform authority or MarketResolution resolver PDA
+    pub authority: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ChallengeEarlyResolution<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Challenger's position in this market (omit for the platform authority)
+    #[account(
+        seeds = [b"user-position", market.key().as_ref(), challenger.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Option<Account<'info, UserPosition>>,
+
+    #[account(
+        init,
+        payer = challenger,
+        space = EarlyResolutionChallenge::LEN,
+        seeds = [b"early-challenge", market.key().as_ref()],
+        bump
+    )]
+    pub challenge: Account<'info, EarlyResolutionChallenge>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleEarlyResolutionChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [b"early-challenge", market.key().as_ref()],
+        bump = challenge.bump,
+        close = challenger
+    )]
+    pub challenge: Account<'info, EarlyResolutionChallenge>,
+
+    /// CHECK: Receives the challenge rent (and the bond if the proposal was overturned)
+    #[account(mut, address = challenge.challenger @ MarketError::Unauthorized)]
+    pub challenger: AccountInfo<'info>,
+
+    /// Protocol treasury (receives a forfeited bond)
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    /// CHECK: Platform wallet from GlobalParameters.authority
+    #[account(mut)]
+    pub platform_wallet: AccountInfo<'info>,
+
+    /// CHECK: Market creator wallet (validated against market.creator)
+    #[account(
+        mut,
+        constraint = creator_wallet.key() == market.creator @ MarketError::Unauthorized
+    )]
+    pub creator_wallet: AccountInfo<'info>,
+
+    /// Protocol treasury (receives treasury fees)
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// MarketResolution resolver PDA (signs via CPI); any signer for finalize_early_resolution
+    pub authority: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub platform_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury token account for the collateral mint (owned by the treasury PDA)
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// MarketSeries of a series market (required for those, omit otherwise)
+    #[account(mut)]
+    pub series: Option<Account<'info, MarketSeries>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPayout<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"user-bet",
+            market.key().as_ref(),
+            bettor.key().as_ref(),
+            &user_bet.bet_index.to_le_bytes()
+        ],
+        bump = user_bet.bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bettor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    // remaining_accounts: (market, user_bet, user_position) per bet, all writable
+}
+
+#[derive(Accounts)]
+#[instruction(pool_id: [u8; 32], nonce: u64)]
+pub struct PlaceParlay<'info> {
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = ParlayPool::LEN,
+        seeds = [b"parlay-pool", pool_id.as_ref()],
+        bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = Parlay::LEN,
+        seeds = [
+            b"parlay",
+            parlay_pool.key().as_ref(),
+            bettor.key().as_ref(),
+            nonce.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage program - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+    // remaining_accounts: leg markets in ascending key order (writable for a new pool)
+}
+
+#[derive(Accounts)]
+pub struct ClaimParlay<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay-pool", parlay_pool.pool_id.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [
+            b"parlay",
+            parlay_pool.key().as_ref(),
+            bettor.key().as_ref(),
+            parlay.nonce.to_le_bytes().as_ref()
+        ],
+        bump = parlay.bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    // remaining_accounts: writable leg markets in pool order (first claim only)
+}
+
+#[derive(Accounts)]
+pub struct SettleParlayPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"parlay-pool", parlay_pool.pool_id.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+    // remaining_accounts: writable leg markets in pool order
+}
+
+#[derive(Accounts)]
+pub struct CloseParlayPool<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"parlay-pool", parlay_pool.pool_id.as_ref()],
+        bump = parlay_pool.bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    /// Protocol treasury (receives the treasury fee and dust)
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Platform wallet (validated against GlobalParameters.authority)
+    #[account(mut)]
+    pub platform_wallet: AccountInfo<'info>,
+
+    /// CHECK: Bettor who opened the pool (paid its rent)
+    #[account(mut, address = parlay_pool.payer @ MarketError::Unauthorized)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// MarketSeries of a series market (required for those, omit otherwise)
+    #[account(mut)]
+    pub series: Option<Account<'info, MarketSeries>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCondition<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.parent_market == Some(parent_market.key()) @ MarketError::InvalidParentMarket
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [b"market", parent_market.market_id.to_le_bytes().as_ref()],
+        bump = parent_market.bump
+    )]
+    pub parent_market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"user-bet",
+            market.key().as_ref(),
+            bettor.key().as_ref(),
+            &user_bet.bet_index.to_le_bytes()
+        ],
+        bump = user_bet.bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bettor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLmsrSurplus<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        constraint = creator.key() == market.creator @ MarketError::Unauthorized
     )]
-    pub market: Account<'info, Market>,
+    pub creator: Signer<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
 
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    pub system_program: Program<'info, System>,
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 #[derive(Accounts)]
-pub struct PlaceBet<'info> {
+pub struct ClaimReferralFees<'info> {
     #[account(
         mut,
         seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
@@ -664,18 +6011,39 @@ pub struct PlaceBet<'info> {
     pub market: Account<'info, Market>,
 
     #[account(
-        init,
-        payer = bettor,
-        space = 8 + 8 + 32 + 1 + 8*5 + 8 + 1 + 2 + 1, // ~140 bytes
-        seeds = [
-            b"user-bet",
-            market.key().as_ref(),
-            bettor.key().as_ref(),
-            &market.total_bets.to_le_bytes()
-        ],
+        mut,
+        seeds = [b"referral-fees", market.key().as_ref(), referrer.key().as_ref()],
+        bump = referral_fees.bump
+    )]
+    pub referral_fees: Account<'info, ReferralFees>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureCollateral<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 * 4 + 1 + 1, // ~74 bytes
+        seeds = [b"collateral-config", collateral_mint.key().as_ref()],
         bump
     )]
-    pub user_bet: Account<'info, UserBet>,
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
 
     /// CHECK: Global parameters from ParameterStorage program - validated via seeds
     #[account(
@@ -685,29 +6053,30 @@ pub struct PlaceBet<'info> {
     )]
     pub global_parameters: AccountInfo<'info>,
 
-    /// CHECK: Bond escrow from BondManager program - validated via seeds
-    /// Story 2.11: Read bond tier for tiered creator fees
-    #[account(
-        seeds = [b"bond-escrow", market.market_id.to_le_bytes().as_ref()],
-        bump,
-        seeds::program = bond_manager_program.key()
-    )]
-    pub bond_escrow: AccountInfo<'info>,
-
     #[account(mut)]
-    pub bettor: Signer<'info>,
+    pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 
     /// CHECK: ParameterStorage program ID
     pub parameter_storage_program: AccountInfo<'info>,
+}
 
-    /// CHECK: BondManager program ID
-    pub bond_manager_program: AccountInfo<'info>,
+#[derive(Accounts)]
+pub struct SetAllowlistRoot<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        has_one = creator @ MarketError::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+
+    pub creator: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveMarket<'info> {
+pub struct SetMarketSizeLimit<'info> {
     #[account(
         mut,
         seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
@@ -723,27 +6092,99 @@ pub struct ResolveMarket<'info> {
     )]
     pub global_parameters: AccountInfo<'info>,
 
-    /// CHECK: Platform wallet from GlobalParameters.authority
-    #[account(mut)]
-    pub platform_wallet: AccountInfo<'info>,
+    pub authority: Signer<'info>,
 
-    /// CHECK: Market creator wallet (validated against market.creator)
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPositionLimit<'info> {
     #[account(
         mut,
-        constraint = creator_wallet.key() == market.creator @ MarketError::Unauthorized
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
     )]
-    pub creator_wallet: AccountInfo<'info>,
+    pub market: Account<'info, Market>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
 
     pub authority: Signer<'info>,
 
     /// CHECK: ParameterStorage program ID
     pub parameter_storage_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Treasury::LEN,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimPayout<'info> {
+pub struct WithdrawTreasury<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: Any wallet chosen by the authority (owner of recipient_token_account for SPL)
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// SPL withdrawals (omit all for SOL)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserBet<'info> {
     #[account(
         mut,
         seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
@@ -753,22 +6194,59 @@ pub struct ClaimPayout<'info> {
 
     #[account(
         mut,
+        close = payer,
         seeds = [
             b"user-bet",
             market.key().as_ref(),
             bettor.key().as_ref(),
-            &user_bet.market_id.to_le_bytes() // Using market_id as bet counter
+            &user_bet.bet_index.to_le_bytes()
         ],
         bump = user_bet.bump
     )]
     pub user_bet: Account<'info, UserBet>,
 
-    #[account(mut)]
-    pub bettor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// CHECK: Bettor - bound by the user_bet seeds
+    pub bettor: AccountInfo<'info>,
+
+    /// CHECK: Rent payer recorded on the bet
+    #[account(mut, address = user_bet.payer @ MarketError::Unauthorized)]
+    pub payer: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CancelMarket<'info> {
+pub struct CloseUserPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"user-position", market.key().as_ref(), bettor.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// CHECK: Bettor - bound by the user_position seeds
+    pub bettor: AccountInfo<'info>,
+
+    /// CHECK: Rent payer recorded on the position
+    #[account(mut, address = user_position.payer @ MarketError::Unauthorized)]
+    pub payer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReferralFees<'info> {
     #[account(
         mut,
         seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
@@ -776,6 +6254,14 @@ pub struct CancelMarket<'info> {
     )]
     pub market: Account<'info, Market>,
 
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"referral-fees", market.key().as_ref(), referral_fees.referrer.as_ref()],
+        bump = referral_fees.bump
+    )]
+    pub referral_fees: Account<'info, ReferralFees>,
+
     /// CHECK: Global parameters from ParameterStorage program - validated via seeds
     #[account(
         seeds = [b"global-parameters"],
@@ -784,16 +6270,19 @@ pub struct CancelMarket<'info> {
     )]
     pub global_parameters: AccountInfo<'info>,
 
-    pub authority: Signer<'info>,
+    /// CHECK: Rent payer recorded in referral_fees
+    #[account(mut, address = referral_fees.payer @ MarketError::Unauthorized)]
+    pub payer: AccountInfo<'info>,
 
     /// CHECK: ParameterStorage program ID
     pub parameter_storage_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimRefund<'info> {
+pub struct CloseMarket<'info> {
     #[account(
         mut,
+        close = creator,
         seeds = [b"market", market.market_id.to_le_bytes().as_ref()],
         bump = market.bump
     )]
@@ -801,24 +6290,60 @@ pub struct ClaimRefund<'info> {
 
     #[account(
         mut,
-        seeds = [
-            b"user-bet",
-            market.key().as_ref(),
-            bettor.key().as_ref(),
-            &user_bet.market_id.to_le_bytes()
-        ],
-        bump = user_bet.bump
+        close = creator,
+        seeds = [b"market-metadata", market.key().as_ref()],
+        bump = market_metadata.bump
     )]
-    pub user_bet: Account<'info, UserBet>,
+    pub market_metadata: Account<'info, MarketMetadata>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Global parameters from ParameterStorage program - validated via seeds
+    #[account(
+        seeds = [b"global-parameters"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_parameters: AccountInfo<'info>,
+
+    /// CHECK: Market creator (paid the market, metadata and vault rent)
+    #[account(mut, address = market.creator @ MarketError::Unauthorized)]
+    pub creator: AccountInfo<'info>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
+
+    /// SPL collateral (omit all for native SOL markets)
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
 
     #[account(mut)]
-    pub bettor: Signer<'info>,
+    pub market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury token account for the collateral mint (owned by the treasury PDA)
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 // ============================================================================
 // External Account Structures (from ParameterStorage)
 // ============================================================================
 
+#[account]
+pub struct GlobalFeatureToggles {
+    pub authority: Pubkey,
+    pub market_creation_enabled: bool,
+    pub betting_enabled: bool,
+    pub resolution_enabled: bool,
+    pub proposals_enabled: bool,
+    pub emergency_pause: bool,
+    pub last_updated: i64,
+    pub version: u32,
+    pub bump: u8,
+}
+
 #[account]
 pub struct GlobalParameters {
     pub authority: Pubkey,
@@ -834,6 +6359,11 @@ pub struct GlobalParameters {
     pub bond_tier_1_lamports: u64,
     pub bond_tier_2_lamports: u64,
     pub bond_tier_3_lamports: u64,
+    pub stale_market_threshold_days: i64,
+    pub voting_weight_mode: u8,
+    pub approved_refund_bps: u16,
+    pub rejected_refund_bps: u16,
+    pub cancelled_refund_bps: u16,
     // Story 2.11: Tiered creator fee percentages by bond tier
     pub low_tier_fee_bps: u16,    // 0.5% = 50 (bonds <100 ZMart / Tier1)
     pub medium_tier_fee_bps: u16, // 1.0% = 100 (bonds 100-499 ZMart / Tier2)
@@ -844,33 +6374,175 @@ pub struct GlobalParameters {
     pub cooldown_until: i64,
     pub version: u32,
     pub bump: u8,
+    // Appended after the original layout (parameter_storage::migrate_parameters)
+    pub exit_fee_bps: u16,        // Charged by sell_position
+    pub no_winner_policy: u8,     // 0 = REFUND_BETTORS, 1 = SWEEP_TO_PLATFORM
+    pub treasury_fee_bps: u16,    // Accrued per bet, paid to the treasury on resolution
+    pub referral_fee_bps: u16,    // Referrer share of platform_fee (bps of the fee)
+    pub claim_period_seconds: i64, // After settlement; close_market sweeps what is left
+    pub betting_cutoff_seconds: i64, // Betting closes this long before end_date
+    pub early_resolution_challenge_seconds: i64, // Before finalize_early_resolution
+    pub max_position_per_wallet_lamports: u64, // Cumulative stake per wallet per market
+    pub early_resolution_challenge_bond_lamports: u64, // Posted by challenge_early_resolution
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct MarketCreatedEvent {
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub title: String,
+    pub market_kind: MarketKind,
+    pub outcome_count: u8,
+    pub collateral_mint: Option<Pubkey>, // None = native SOL
+    pub pricing_mode: PricingMode,
+    pub end_date: i64,
+    pub betting_closes_at: i64,
+    pub category: MarketCategory,
+    pub metadata_hash: [u8; 32], // Verifies the off-chain metadata JSON
+    pub parent_market: Option<Pubkey>, // Conditional markets only
+    pub required_outcome: Option<BetSide>,
+    pub allowlist_root: Option<[u8; 32]>, // Private markets only
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeriesCreatedEvent {
+    pub series_id: u32,
+    pub creator: Pubkey,
+    pub title_pattern: String,
+    pub duration_seconds: i64,
+    pub first_end_date: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeriesUpdatedEvent {
+    pub series_id: u32,
+    pub title_pattern: String,
+    pub duration_seconds: i64,
+    pub active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeriesMarketSpawnedEvent {
+    pub series_id: u32,
+    pub market_id: u64,
+    pub index: u32,
+    pub end_date: i64,
+    pub cranker: Pubkey,
+    pub rent_reimbursed: u64, // Paid to the cranker from the series balance
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeriesClosedEvent {
+    pub series_id: u32,
+    pub markets_created: u32,
+    pub total_volume: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetPlacedEvent {
+    pub market_id: u64,
+    pub bettor: Pubkey,
+    pub bet_index: u64,
+    pub bet_side: BetSide,
+    pub amount: u64,
+    pub amount_to_pool: u64,
+    pub platform_fee: u64,
+    pub creator_fee: u64,
+    pub treasury_fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub referral_fee: u64, // Part of platform_fee owed to the referrer
+    pub yes_pool: u64,
+    pub no_pool: u64,
+    pub outcome_odds: Vec<u16>, // Basis points per outcome ([yes, no] for binary)
+    pub shares: u64,            // LMSR markets only
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquiditySeededEvent {
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub yes_amount: u64,
+    pub no_amount: u64,
+    pub yes_odds: u16, // Starting YES odds (bps)
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionSoldEvent {
+    pub market_id: u64,
+    pub bettor: Pubkey,
+    pub bet_side: BetSide,
+    pub stake_sold: u64,
+    pub shares_sold: u64,       // LMSR markets only
+    pub gross_proceeds: u64,    // Sale value before the exit fee
+    pub exit_fee: u64,
+    pub fees_refunded: u64,     // Entry fees of the sold portion
+    pub net_proceeds: u64,      // gross_proceeds - exit_fee + fees_refunded
+    pub remaining_stake: u64,
+    pub yes_pool: u64,
+    pub no_pool: u64,
+    pub outcome_odds: Vec<u16>, // Basis points per outcome after the sale
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyResolutionProposedEvent {
+    pub market_id: u64,
+    pub proposer: Pubkey,
+    pub outcome: Option<BetSide>, // None = cancel
+    pub halted_at: i64,
+    pub challenge_ends_at: i64,
+}
+
+#[event]
+pub struct EarlyResolutionChallengedEvent {
+    pub market_id: u64,
+    pub challenger: Pubkey,
+    pub proposed_outcome: Option<BetSide>,
+    pub bond: u64,
+    pub timestamp: i64,
 }
 
-// ============================================================================
-// Events
-// ============================================================================
+#[event]
+pub struct EarlyResolutionChallengeSettledEvent {
+    pub market_id: u64,
+    pub challenger: Pubkey,
+    pub proposed_outcome: Option<BetSide>,
+    pub resolved_outcome: Option<BetSide>,
+    pub bond_forfeited: bool, // true = vote matched the proposal
+    pub bond: u64,
+    pub timestamp: i64,
+}
 
 #[event]
-pub struct MarketCreatedEvent {
+pub struct DecisionTimeReportedEvent {
     pub market_id: u64,
-    pub creator: Pubkey,
-    pub title: String,
-    pub end_date: i64,
+    pub decided_at: i64,
+    pub void_from_bet: u64, // First voided UserBet.sequence
+    pub bets_to_void: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct BetPlacedEvent {
+pub struct BetVoidedEvent {
     pub market_id: u64,
     pub bettor: Pubkey,
-    pub bet_side: BetSide,
-    pub amount: u64,
-    pub amount_to_pool: u64,
-    pub platform_fee: u64,
-    pub creator_fee: u64,
-    pub yes_pool: u64,
-    pub no_pool: u64,
-    pub yes_odds: u16,
+    pub bet_index: u64,
+    pub sequence: u64,
+    pub stake: u64,         // Refunded on claim
+    pub fees_refunded: u64, // Reversed fee accruals, refunded with the stake
+    pub pending_voids: u64,
     pub timestamp: i64,
 }
 
@@ -883,6 +6555,16 @@ pub struct MarketResolvedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ScalarMarketResolvedEvent {
+    pub market_id: u64,
+    pub resolved_value: i64,
+    pub long_pool: u64,
+    pub short_pool: u64,
+    pub long_payout_bps: u16, // Share of total pool paid to long positions
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct PayoutClaimedEvent {
     pub market_id: u64,
@@ -891,6 +6573,29 @@ pub struct PayoutClaimedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BatchClaimedEvent {
+    pub bettor: Pubkey,
+    pub payouts_claimed: u32,
+    pub refunds_claimed: u32,
+    pub bets_skipped: u32,
+    pub total_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NoWinnerResolutionEvent {
+    pub market_id: u64,
+    pub outcome: BetSide,
+    pub policy: NoWinnerPolicy,
+    pub total_pool: u64,
+    pub swept_amount: u64,  // 0 when the market was made refundable
+    pub platform_fees: u64,
+    pub creator_fees: u64,
+    pub treasury_fees: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct MarketCancelledEvent {
     pub market_id: u64,
@@ -900,6 +6605,48 @@ pub struct MarketCancelledEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ConditionSettledEvent {
+    pub market_id: u64,
+    pub parent_market_id: u64,
+    pub required_outcome: Option<BetSide>,
+    pub parent_outcome: Option<BetSide>, // None if the parent was cancelled
+    pub condition_met: bool,             // false = parent went the other way, market cancelled
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlayPlacedEvent {
+    pub pool: Pubkey,
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub legs: Vec<Pubkey>,
+    pub sides: Vec<BetSide>,
+    pub amount: u64,
+    pub stake: u64, // After platform and treasury fees
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlayPoolSettledEvent {
+    pub pool: Pubkey,
+    pub winning_combination: u8,
+    pub cancelled_legs: u8,
+    pub total_stake: u64,
+    pub winning_stake: u64, // 0 = no winners, every ticket refunded (fees included)
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlayClaimedEvent {
+    pub pool: Pubkey,
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub stake: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct RefundClaimedEvent {
     pub market_id: u64,
@@ -908,6 +6655,75 @@ pub struct RefundClaimedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct LmsrSurplusClaimedEvent {
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeesClaimedEvent {
+    pub market_id: u64,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub bets_referred: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistUpdatedEvent {
+    pub market_id: u64,
+    pub allowlist_root: Option<[u8; 32]>, // None = open to all
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketSizeLimitUpdatedEvent {
+    pub market_id: u64,
+    pub max_market_size: Option<u64>, // None = default limit
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLimitUpdatedEvent {
+    pub market_id: u64,
+    pub max_position_per_wallet: Option<u64>, // None = default limit
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawalEvent {
+    pub authority: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Option<Pubkey>, // None = SOL
+    pub amount: u64,
+    pub remaining_balance: u64, // Withdrawable balance left (SOL: above rent exemption)
+    pub withdrawal_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketClosedEvent {
+    pub market_id: u64,
+    pub creator: Pubkey,          // Received the account (and vault) rent
+    pub mint: Option<Pubkey>,     // None = SOL
+    pub dust_swept: u64,          // Collateral left after the claim period, sent to the treasury
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollateralConfiguredEvent {
+    pub mint: Pubkey,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub max_market_size: u64,
+    pub max_position_per_wallet: u64,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -982,4 +6798,591 @@ pub enum MarketError {
 
     #[msg("Cannot cancel market before end date")]
     CannotCancelBeforeEndDate,
+
+    #[msg("Invalid outcome count: binary markets take no labels, categorical markets take 2-16")]
+    InvalidOutcomeCount,
+
+    #[msg("Invalid outcome label: must be 1-32 characters")]
+    InvalidOutcomeLabel,
+
+    #[msg("Invalid outcome for this market")]
+    InvalidOutcome,
+
+    #[msg("Invalid scalar bounds: lower_bound must be below upper_bound")]
+    InvalidScalarBounds,
+
+    #[msg("Scalar markets must be resolved with resolve_scalar_market")]
+    ScalarMarketRequiresValue,
+
+    #[msg("Market is not a scalar market")]
+    NotScalarMarket,
+
+    #[msg("Missing collateral accounts for SPL-denominated market")]
+    MissingCollateralAccounts,
+
+    #[msg("Collateral account does not match market mint, vault or owner")]
+    InvalidCollateralAccount,
+
+    #[msg("Collateral mint is disabled")]
+    CollateralDisabled,
+
+    #[msg("Invalid bet limits: require 0 < min_bet <= max_bet <= max_market_size")]
+    InvalidBetLimits,
+
+    #[msg("LMSR pricing is only supported for binary markets")]
+    LmsrRequiresBinaryMarket,
+
+    #[msg("Invalid LMSR liquidity parameter: must be greater than 0")]
+    InvalidLiquidityParameter,
+
+    #[msg("Creator deposit does not cover the LMSR maximum loss")]
+    InsufficientLiquiditySubsidy,
+
+    #[msg("Trade too large for LMSR liquidity")]
+    LmsrTradeTooLarge,
+
+    #[msg("LMSR math error")]
+    LmsrMathError,
+
+    #[msg("Market does not use LMSR pricing")]
+    NotLmsrMarket,
+
+    #[msg("Invalid sell amount: must be between 1 and the position's pooled stake")]
+    InvalidSellAmount,
+
+    #[msg("Sale proceeds below the requested minimum")]
+    SaleProceedsBelowMinimum,
+
+    #[msg("Pool underflow")]
+    PoolUnderflow,
+
+    #[msg("Unique bettors counter overflow")]
+    UniqueBettorsOverflow,
+
+    #[msg("Invalid claim batch: expected 1-20 writable (market, user_bet, user_position) triples")]
+    InvalidClaimBatch,
+
+    // No longer returned (claim_many skips token-collateral bets); kept so later error codes don't shift
+    #[msg("claim_many only supports SOL markets; claim token-collateral bets individually")]
+    ClaimManySolOnly,
+
+    #[msg("Invalid feature toggles account: must be the ParameterStorage global-toggles PDA")]
+    InvalidFeatureToggles,
+
+    #[msg("Protocol is under emergency pause")]
+    EmergencyPaused,
+
+    #[msg("Market creation is disabled")]
+    MarketCreationDisabled,
+
+    #[msg("Betting is disabled")]
+    BettingDisabled,
+
+    #[msg("Market resolution is disabled")]
+    ResolutionDisabled,
+
+    #[msg("Market duration outside the allowed window (see logs for limits)")]
+    InvalidMarketDuration,
+
+    #[msg("Bet would exceed the market size limit (compared values: remaining headroom, largest bet that fits)")]
+    MarketSizeExceeded,
+
+    #[msg("Invalid market size limit: must be greater than 0")]
+    InvalidMarketSizeLimit,
+
+    #[msg("Bet would exceed the per-wallet position limit (compared values: remaining allowance, limit)")]
+    PositionLimitExceeded,
+
+    #[msg("Invalid position limit: must be greater than 0")]
+    InvalidPositionLimit,
+
+    #[msg("Withdrawal amount must be greater than 0")]
+    InvalidWithdrawalAmount,
+
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasuryBalance,
+
+    #[msg("Bettor cannot refer their own bet")]
+    SelfReferral,
+
+    #[msg("Referred bets need both the referrer and referral_fees accounts")]
+    MissingReferralAccounts,
+
+    #[msg("No referral fees to claim")]
+    NoReferralFees,
+
+    #[msg("Account is not finished yet and cannot be closed")]
+    AccountNotClosable,
+
+    #[msg("Claim period has not ended")]
+    ClaimPeriodNotEnded,
+
+    #[msg("Market still has open bet, position or referral accounts")]
+    MarketHasOpenAccounts,
+
+    #[msg("Market is still a leg of an unsettled parlay pool or the parent of an unsettled conditional market")]
+    MarketHasDependents,
+
+    #[msg("Invalid slippage bounds: need min <= max <= 10000 bps")]
+    InvalidSlippageBounds,
+
+    #[msg("Odds moved outside the accepted range (see logs)")]
+    SlippageExceeded,
+
+    #[msg("Betting has closed for this market")]
+    BettingClosed,
+
+    #[msg("Decision time already reported for this market")]
+    DecisionTimeAlreadyReported,
+
+    #[msg("Decision time must be between market creation and now")]
+    InvalidDecisionTime,
+
+    #[msg("Boundary bets do not match the reported decision time")]
+    InvalidVoidBoundary,
+
+    #[msg("Retroactive void is not supported for LMSR markets")]
+    LmsrVoidNotSupported,
+
+    #[msg("Bet was not placed after the reported decision time")]
+    BetNotVoidable,
+
+    #[msg("Bet already voided")]
+    BetAlreadyVoided,
+
+    #[msg("Bets placed after the decision time must be voided before resolution")]
+    VoidedBetsPending,
+
+    #[msg("An early resolution is already pending for this market")]
+    EarlyResolutionPending,
+
+    #[msg("No early resolution has been proposed for this market")]
+    NoEarlyResolution,
+
+    #[msg("Early resolution challenge period has ended")]
+    ChallengePeriodEnded,
+
+    #[msg("Early resolution challenge period has not ended")]
+    ChallengePeriodNotEnded,
+
+    #[msg("Early resolution was challenged and is settled by vote")]
+    EarlyResolutionChallenged,
+
+    #[msg("Invalid tags: at most 5, each 1-32 characters")]
+    InvalidTags,
+
+    #[msg("Resolution source URI exceeds 200 characters")]
+    InvalidResolutionSource,
+
+    #[msg("Market ids with the top bit set are reserved for series markets")]
+    ReservedMarketId,
+
+    #[msg("Series id must be below 2^31")]
+    InvalidSeriesId,
+
+    #[msg("Series creator fee exceeds the high-tier bond fee")]
+    InvalidSeriesFee,
+
+    #[msg("Series is paused")]
+    SeriesPaused,
+
+    #[msg("Previous market in the series has not ended")]
+    PreviousMarketNotEnded,
+
+    #[msg("Series balance cannot cover the new market's rent")]
+    SeriesUnderfunded,
+
+    #[msg("Series account missing or does not match the market")]
+    InvalidSeriesAccount,
+
+    #[msg("Series counter overflow")]
+    SeriesOverflow,
+
+    #[msg("Conditional markets need an active binary/categorical parent and a valid required outcome")]
+    InvalidCondition,
+
+    #[msg("Parent market account missing or does not match the market")]
+    InvalidParentMarket,
+
+    #[msg("Parent market did not resolve to the required outcome")]
+    ConditionFailed,
+
+    #[msg("Parent market has not settled yet")]
+    ParentNotSettled,
+
+    #[msg("Condition already settled for this market")]
+    ConditionAlreadySettled,
+
+    #[msg("Conditional market cannot settle before its condition is met")]
+    ConditionNotSettled,
+
+    #[msg("Parlay legs must be 2-4 distinct binary markets in ascending order matching the pool")]
+    InvalidParlayLegs,
+
+    #[msg("Every parlay leg must be resolved or cancelled")]
+    ParlayLegsNotSettled,
+
+    #[msg("Parlay pool is already settled")]
+    ParlayPoolAlreadySettled,
+
+    #[msg("Only binary parimutuel markets can be seeded")]
+    InvalidSeedMarket,
+
+    #[msg("Liquidity can only be seeded before the first bet")]
+    MarketAlreadySeeded,
+
+    #[msg("Seed YES probability must be between 1 and 9999 bps")]
+    InvalidSeedProbability,
+
+    #[msg("Bettor is not on this market's allowlist")]
+    NotAllowlisted,
+
+    #[msg("Allowlist can only change before the first bet")]
+    AllowlistLocked,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An account with every field zeroed (Vec/Option/enum fields at their first value)
+    fn zeroed<T: AccountDeserialize + Discriminator>() -> T {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.resize(4096, 0);
+        T::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    fn resolved_scalar(lower_bound: i64, upper_bound: i64, value: i64) -> Market {
+        let mut market: Market = zeroed();
+        market.market_kind = MarketKind::Scalar { lower_bound, upper_bound };
+        market.status = MarketStatus::Resolved;
+        market.resolved_value = Some(value);
+        market
+    }
+
+    #[test]
+    fn scalar_payout_is_linear_between_bounds() {
+        assert_eq!(resolved_scalar(0, 100, 0).scalar_long_payout_bps().unwrap(), 0);
+        assert_eq!(resolved_scalar(0, 100, 25).scalar_long_payout_bps().unwrap(), 2500);
+        assert_eq!(resolved_scalar(0, 100, 100).scalar_long_payout_bps().unwrap(), 10000);
+        assert_eq!(resolved_scalar(-50, 50, 0).scalar_long_payout_bps().unwrap(), 5000);
+    }
+
+    #[test]
+    fn scalar_payout_clamps_to_bounds() {
+        assert_eq!(resolved_scalar(0, 100, -1).scalar_long_payout_bps().unwrap(), 0);
+        assert_eq!(resolved_scalar(0, 100, 101).scalar_long_payout_bps().unwrap(), 10000);
+        assert_eq!(
+            resolved_scalar(i64::MIN, i64::MAX, i64::MAX).scalar_long_payout_bps().unwrap(),
+            10000
+        );
+        assert_eq!(
+            resolved_scalar(i64::MIN, i64::MAX, i64::MIN).scalar_long_payout_bps().unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn scalar_payout_requires_a_resolved_scalar_market() {
+        let mut market = resolved_scalar(0, 100, 50);
+        market.resolved_value = None;
+        assert!(market.scalar_long_payout_bps().is_err());
+
+        let mut binary: Market = zeroed();
+        binary.resolved_value = Some(50);
+        assert!(binary.scalar_long_payout_bps().is_err());
+    }
+
+    /// |actual - expected| within `tolerance` (fixed-point units)
+    fn assert_close(actual: i128, expected: i128, tolerance: i128) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn fp_exp_matches_known_values() {
+        assert_eq!(fp_exp(0), Some(LMSR_SCALE));
+        assert_close(fp_exp(LMSR_SCALE).unwrap(), 2_718_281_828_459, 10);
+        assert_close(fp_exp(-LMSR_SCALE).unwrap(), 367_879_441_171, 10);
+        assert_close(fp_exp(LMSR_LN2).unwrap(), 2 * LMSR_SCALE, 10);
+    }
+
+    #[test]
+    fn fp_exp_rejects_exponents_out_of_range() {
+        assert!(fp_exp(LMSR_MAX_EXPONENT).is_some());
+        assert!(fp_exp(-LMSR_MAX_EXPONENT).is_some());
+        assert_eq!(fp_exp(LMSR_MAX_EXPONENT + 1), None);
+        assert_eq!(fp_exp(-LMSR_MAX_EXPONENT - 1), None);
+        assert_eq!(fp_exp_neg(-LMSR_MAX_EXPONENT - 1), 0);
+    }
+
+    #[test]
+    fn fp_ln_matches_known_values_and_inverts_fp_exp() {
+        assert_eq!(fp_ln(LMSR_SCALE), Some(0));
+        assert_close(fp_ln(2 * LMSR_SCALE).unwrap(), LMSR_LN2, 10);
+        assert_close(fp_ln(LMSR_SCALE / 2).unwrap(), -LMSR_LN2, 10);
+        for x in [-15 * LMSR_SCALE, -LMSR_SCALE, LMSR_SCALE / 3, 7 * LMSR_SCALE, 19 * LMSR_SCALE] {
+            let roundtrip = fp_ln(fp_exp(x).unwrap()).unwrap();
+            // Relative error ~1e-10 once e^x is tiny
+            assert_close(roundtrip, x, LMSR_SCALE / 1_000);
+        }
+    }
+
+    #[test]
+    fn fp_ln_rejects_non_positive_input() {
+        assert_eq!(fp_ln(0), None);
+        assert_eq!(fp_ln(-LMSR_SCALE), None);
+        assert!(fp_ln(1).is_some());
+        assert!(fp_ln(i128::MAX / LMSR_SCALE).is_some());
+    }
+
+    #[test]
+    fn lmsr_price_starts_even_and_sums_to_one() {
+        let b = 1_000_000_000;
+        assert_eq!(lmsr_price_bps(0, 0, b), 5000);
+        for (yes, no) in [(0, 2 * b), (3 * b, b), (b / 2, 0)] {
+            let total = lmsr_price_bps(yes, no, b) as i32 + lmsr_price_bps(no, yes, b) as i32;
+            assert!((9998..=10000).contains(&total), "prices sum to {}", total);
+        }
+    }
+
+    #[test]
+    fn lmsr_shares_grow_with_cost_at_a_rising_price() {
+        let b = 1_000_000_000;
+        let mut last_shares = 0;
+        let mut last_avg_price = 0;
+        for cost in [1_000_000, 10_000_000, 100_000_000, 1_000_000_000, 5_000_000_000] {
+            let shares = lmsr_shares_for_cost(0, 0, b, cost).unwrap();
+            assert!(shares > last_shares, "shares must grow with cost");
+            // Average price per share rises with size and stays above the spot price
+            let avg_price = cost as u128 * 1_000_000 / shares as u128;
+            assert!(avg_price > last_avg_price);
+            assert!(avg_price >= 500_000);
+            assert!(avg_price < 1_000_000);
+            last_shares = shares;
+            last_avg_price = avg_price;
+        }
+
+        // Buying a side moves its price up
+        let shares = lmsr_shares_for_cost(0, 0, b, b).unwrap();
+        assert!(lmsr_price_bps(shares, 0, b) > 5000);
+    }
+
+    #[test]
+    fn lmsr_round_trip_never_pays_out_more_than_it_cost() {
+        let b = 1_000_000_000;
+        for (side, other, cost) in [(0, 0, b), (2 * b, 0, b / 3), (0, 3 * b, 2 * b)] {
+            let shares = lmsr_shares_for_cost(side, other, b, cost).unwrap();
+            let proceeds = lmsr_proceeds_for_shares(side + shares, other, b, shares).unwrap();
+            assert!(proceeds <= cost, "sold {} for {} after paying {}", shares, proceeds, cost);
+        }
+    }
+
+    #[test]
+    fn lmsr_rejects_trades_past_the_exponent_bound() {
+        let b = 1_000_000;
+        assert_eq!(lmsr_shares_for_cost(0, 0, b, 25 * b), None);
+        assert!(lmsr_shares_for_cost(0, 0, b, 10 * b).is_some());
+    }
+
+    #[test]
+    fn sale_gains_come_only_from_forfeited_stake() {
+        // YES at 25%, bought at 20%: worth 125 per 100 staked
+        let mut market: Market = zeroed();
+        market.yes_pool = 1_000;
+        market.no_pool = 3_000;
+        assert_eq!(market.sell_position(&BetSide::Yes, 100, 0, 2000).unwrap(), 100);
+        assert_eq!((market.yes_pool, market.no_pool, market.forfeited_stake), (900, 3_000, 0));
+
+        // A discount is forfeited to the market and funds later gains
+        // (YES now at 23.07%)
+        assert_eq!(market.sell_position(&BetSide::Yes, 100, 0, 5000).unwrap(), 46);
+        assert_eq!(market.forfeited_stake, 54);
+        // (YES now at 21.05%)
+        assert_eq!(market.sell_position(&BetSide::Yes, 100, 0, 2000).unwrap(), 105);
+        assert_eq!(market.forfeited_stake, 49);
+        assert_eq!((market.yes_pool, market.no_pool), (700, 3_000));
+    }
+
+    #[test]
+    fn lmsr_sale_gains_never_touch_the_other_pool() {
+        let b = 1_000_000_000;
+        let first_cost = b / 2;
+        let first_shares = lmsr_shares_for_cost(0, 0, b, first_cost).unwrap();
+        let second_shares = lmsr_shares_for_cost(first_shares, 0, b, b).unwrap();
+
+        let mut market: Market = zeroed();
+        market.pricing_mode = PricingMode::Lmsr { liquidity_b: b };
+        market.yes_shares = first_shares + second_shares;
+        market.yes_pool = first_cost + b;
+        market.no_pool = 7;
+        market.lmsr_subsidy = 1_000;
+
+        // The price rose: the first buyer's gain is capped by the subsidy
+        let proceeds = market
+            .sell_position(&BetSide::Yes, first_cost, first_shares, 0)
+            .unwrap();
+        assert_eq!(proceeds, first_cost + 1_000);
+        assert_eq!((market.yes_pool, market.no_pool, market.lmsr_subsidy), (b, 7, 0));
+    }
+
+    /// Settled two-leg pool; combination bit i set = No on leg i
+    fn settled_pool(
+        combination_stakes: [u64; 4],
+        winning_combination: u8,
+        cancelled_legs: u8,
+    ) -> ParlayPool {
+        let mut pool: ParlayPool = zeroed();
+        pool.legs = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        pool.combination_stakes = combination_stakes.to_vec();
+        pool.total_stake = combination_stakes.iter().sum();
+        pool.winning_combination = winning_combination;
+        pool.cancelled_legs = cancelled_legs;
+        pool.winning_stake = (0..4u8)
+            .filter(|combination| pool.is_winning(*combination))
+            .map(|combination| combination_stakes[combination as usize])
+            .sum();
+        pool.settled = true;
+        pool
+    }
+
+    /// Ticket whose amount includes a 2% fee on top of its stake
+    fn ticket(combination: u8, stake: u64) -> Parlay {
+        let mut parlay: Parlay = zeroed();
+        parlay.combination = combination;
+        parlay.stake = stake;
+        parlay.amount = stake * 100 / 98;
+        parlay
+    }
+
+    #[test]
+    fn parlay_winners_split_the_whole_pool() {
+        // Yes/Yes won; 100 of 1000 staked on it
+        let pool = settled_pool([100, 200, 300, 400], 0b00, 0);
+        assert_eq!(pool.winning_stake, 100);
+        assert_eq!(pool.payout_for(&ticket(0b00, 40)).unwrap(), 400);
+        assert_eq!(pool.payout_for(&ticket(0b00, 60)).unwrap(), 600);
+        assert_eq!(pool.payout_for(&ticket(0b01, 200)).unwrap(), 0);
+        assert_eq!(pool.payout_for(&ticket(0b11, 400)).unwrap(), 0);
+    }
+
+    #[test]
+    fn parlay_cancelled_legs_drop_out() {
+        // Leg 0 cancelled, leg 1 resolved No: Yes/No and No/No both win
+        let pool = settled_pool([100, 200, 300, 400], 0b10, 0b01);
+        assert!(pool.is_winning(0b10));
+        assert!(pool.is_winning(0b11));
+        assert!(!pool.is_winning(0b00));
+        assert_eq!(pool.winning_stake, 700);
+        assert_eq!(pool.payout_for(&ticket(0b10, 300)).unwrap(), 300 * 1000 / 700);
+        assert_eq!(pool.payout_for(&ticket(0b00, 100)).unwrap(), 0);
+    }
+
+    #[test]
+    fn parlay_amounts_are_refunded_when_nobody_won() {
+        let pool = settled_pool([0, 196, 294, 392], 0b00, 0);
+        assert_eq!(pool.winning_stake, 0);
+        assert!(pool.refunds_all());
+        assert_eq!(pool.payout_for(&ticket(0b01, 196)).unwrap(), 200);
+        assert_eq!(pool.payout_for(&ticket(0b11, 147)).unwrap(), 150);
+    }
+
+    #[test]
+    fn parlay_amounts_are_refunded_when_every_leg_cancelled() {
+        let pool = settled_pool([98, 196, 294, 392], 0b00, 0b11);
+        assert!(pool.refunds_all());
+        assert_eq!(pool.payout_for(&ticket(0b00, 98)).unwrap(), 100);
+        assert_eq!(pool.payout_for(&ticket(0b10, 294)).unwrap(), 300);
+
+        // One leg left: normal payouts, fees kept
+        let pool = settled_pool([98, 196, 294, 392], 0b00, 0b01);
+        assert!(!pool.refunds_all());
+        assert_eq!(pool.payout_for(&ticket(0b00, 98)).unwrap(), 98 * 980 / 294);
+    }
+
+    #[test]
+    fn parlay_payouts_never_exceed_the_pool() {
+        let pool = settled_pool([333, 1, 1, 1], 0b00, 0);
+        let paid: u64 = [111, 111, 111]
+            .iter()
+            .map(|stake| pool.payout_for(&ticket(0b00, *stake)).unwrap())
+            .sum();
+        assert!(paid <= pool.total_stake);
+    }
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        solana_keccak_hasher::hashv(&[&first, &second]).to_bytes()
+    }
+
+    /// Sorted-pair tree levels, leaves first; an unpaired node is promoted
+    fn merkle_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(*a, *b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    fn merkle_proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in &levels[..levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+
+    #[test]
+    fn merkle_proofs_verify_for_every_member() {
+        let members: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let levels = merkle_levels(members.iter().map(allowlist_leaf).collect());
+        let root = levels.last().unwrap()[0];
+        for (index, member) in members.iter().enumerate() {
+            let proof = merkle_proof(&levels, index);
+            assert!(verify_merkle_proof(&proof, &root, allowlist_leaf(member)));
+        }
+    }
+
+    #[test]
+    fn merkle_proofs_reject_tampering() {
+        let members: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let levels = merkle_levels(members.iter().map(allowlist_leaf).collect());
+        let root = levels.last().unwrap()[0];
+        let mut proof = merkle_proof(&levels, 1);
+        let leaf = allowlist_leaf(&members[1]);
+
+        let outsider = allowlist_leaf(&Pubkey::new_unique());
+        assert!(!verify_merkle_proof(&proof, &root, outsider));
+        assert!(!verify_merkle_proof(&proof, &[0u8; 32], leaf));
+
+        proof[0][0] ^= 1;
+        assert!(!verify_merkle_proof(&proof, &root, leaf));
+    }
+
+    #[test]
+    fn merkle_single_leaf_root_is_the_leaf() {
+        let leaf = allowlist_leaf(&Pubkey::new_unique());
+        let levels = merkle_levels(vec![leaf]);
+        assert_eq!(levels.last().unwrap()[0], leaf);
+        assert!(verify_merkle_proof(&[], &leaf, leaf));
+        assert!(!verify_merkle_proof(&[], &leaf, allowlist_leaf(&Pubkey::new_unique())));
+    }
 }
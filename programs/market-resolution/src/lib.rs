@@ -1,7 +1,13 @@
 use anchor_lang::prelude::*;
+use core_markets::program::CoreMarkets;
+use core_markets::{BetSide, RESOLVER_AUTHORITY_SEED};
+use std::str::FromStr;
 
 declare_id!("Hcxxt6W1HmKQmnUvqpgzNEqVG611Yzt2i4DUvwvkLRf2");
 
+/// Parameter Storage Program ID string (owner of the feature toggles PDA)
+const PARAMETER_STORAGE_PROGRAM_ID_STR: &str = "J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD";
+
 /// BMAD-Zmart Market Resolution
 ///
 /// Community voting system for determining market outcomes.
@@ -12,12 +18,18 @@ declare_id!("Hcxxt6W1HmKQmnUvqpgzNEqVG611Yzt2i4DUvwvkLRf2");
 /// - 48-hour dispute window enforcement
 /// - Vote aggregation and outcome determination
 /// - Admin override for progressive decentralization (Epic 1)
+/// - Finalized outcomes resolve/cancel the CoreMarkets market via CPI,
+///   signed by the resolver PDA: ["resolver-authority"]
+/// - On-chain vote tallies are Yes/No/Cancel; categorical (outcome index) and
+///   scalar (observed value) markets settle through admin_override_resolution
 ///
 /// Security:
 /// - One vote per wallet per market
 /// - Dispute window validation
 /// - Admin-only override during MVP phase
 /// - Cross-program resolution of CoreMarkets
+/// - Voting and settlement instructions honour the resolution toggle and emergency pause
+/// - Vote records and resolution state close after finalization (rent to the payer)
 #[program]
 pub mod market_resolution {
     use super::*;
@@ -30,6 +42,8 @@ pub mod market_resolution {
         vote_choice: VoteChoice,
         vote_weight: u64,
     ) -> Result<()> {
+        require_resolution_enabled(&ctx.accounts.global_toggles)?;
+
         let vote_record = &mut ctx.accounts.vote_record;
         let resolution_state = &mut ctx.accounts.resolution_state;
         let clock = Clock::get()?;
@@ -53,6 +67,9 @@ pub mod market_resolution {
             VoteChoice::Yes => resolution_state.yes_votes += vote_weight,
             VoteChoice::No => resolution_state.no_votes += vote_weight,
             VoteChoice::Cancel => resolution_state.cancel_votes += vote_weight,
+            VoteChoice::Outcome(_) | VoteChoice::ScalarValue(_) => {
+                return err!(ResolutionError::InvalidVoteChoice)
+            }
         }
 
         resolution_state.total_voters += 1;
@@ -82,6 +99,8 @@ pub mod market_resolution {
         ctx: Context<InitializeResolution>,
         market_id: u64,
     ) -> Result<()> {
+        require_resolution_enabled(&ctx.accounts.global_toggles)?;
+
         let resolution_state = &mut ctx.accounts.resolution_state;
         let params = &ctx.accounts.global_parameters;
         let clock = Clock::get()?;
@@ -98,6 +117,8 @@ pub mod market_resolution {
         resolution_state.dispute_window_ends_at =
             clock.unix_timestamp + params.dispute_window_seconds;
         resolution_state.finalized_at = None;
+        resolution_state.payer = ctx.accounts.authority.key();
+        resolution_state.closed_vote_records = 0;
         resolution_state.bump = ctx.bumps.resolution_state;
 
         emit!(ResolutionInitializedEvent {
@@ -113,8 +134,11 @@ pub mod market_resolution {
 
     /// Finalize market resolution
     ///
-    /// Aggregates votes and determines outcome after dispute window.
+    /// Aggregates votes and determines outcome after dispute window,
+    /// then resolves (Yes/No) or cancels (Cancel) the CoreMarkets market.
     pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+        require_resolution_enabled(&ctx.accounts.core_markets.global_toggles)?;
+
         let resolution_state = &mut ctx.accounts.resolution_state;
         let clock = Clock::get()?;
 
@@ -161,6 +185,13 @@ pub mod market_resolution {
             resolution_state.cancel_votes
         );
 
+        let market_id = resolution_state.market_id;
+        ctx.accounts.core_markets.settle_market(
+            market_id,
+            &outcome,
+            ctx.bumps.core_markets.resolver_authority,
+        )?;
+
         Ok(())
     }
 
@@ -168,10 +199,14 @@ pub mod market_resolution {
     ///
     /// Progressive decentralization: admin can override during Epic 1 MVP.
     /// Epic 2 removes this and fully decentralizes to community voting.
+    /// The overridden outcome is applied to the CoreMarkets market via CPI:
+    /// Outcome(index) for categorical markets, ScalarValue for scalar ones.
     pub fn admin_override_resolution(
         ctx: Context<AdminOverrideResolution>,
         outcome: VoteChoice,
     ) -> Result<()> {
+        require_resolution_enabled(&ctx.accounts.core_markets.global_toggles)?;
+
         let resolution_state = &mut ctx.accounts.resolution_state;
         let params = &ctx.accounts.global_parameters;
         let clock = Clock::get()?;
@@ -182,6 +217,12 @@ pub mod market_resolution {
             ResolutionError::Unauthorized
         );
 
+        // Validate not already finalized (market was already settled via CPI)
+        require!(
+            !resolution_state.is_finalized,
+            ResolutionError::MarketAlreadyFinalized
+        );
+
         // Override outcome
         resolution_state.is_finalized = true;
         resolution_state.outcome = Some(outcome.clone());
@@ -200,6 +241,13 @@ pub mod market_resolution {
             outcome
         );
 
+        let market_id = resolution_state.market_id;
+        ctx.accounts.core_markets.settle_market(
+            market_id,
+            &outcome,
+            ctx.bumps.core_markets.resolver_authority,
+        )?;
+
         Ok(())
     }
 
@@ -214,6 +262,8 @@ pub mod market_resolution {
         ctx: Context<PostVoteResult>,
         data: PostVoteResultData,
     ) -> Result<()> {
+        require_resolution_enabled(&ctx.accounts.global_toggles)?;
+
         let vote_result = &mut ctx.accounts.vote_result;
         let params = &ctx.accounts.global_parameters;
         let clock = Clock::get()?;
@@ -258,12 +308,153 @@ pub mod market_resolution {
 
         Ok(())
     }
+
+    /// Close a vote record once its market's resolution is finalized
+    ///
+    /// Permissionless; rent returns to the voter.
+    pub fn close_vote_record(ctx: Context<CloseVoteRecord>) -> Result<()> {
+        let resolution_state = &mut ctx.accounts.resolution_state;
+
+        require!(
+            resolution_state.is_finalized,
+            ResolutionError::ResolutionNotFinalized
+        );
+
+        resolution_state.closed_vote_records += 1;
+
+        msg!(
+            "Closed vote record of {} for market {}",
+            ctx.accounts.voter.key(),
+            resolution_state.market_id
+        );
+
+        Ok(())
+    }
+
+    /// Close a finalized resolution state after all its vote records are closed
+    ///
+    /// Permissionless; rent returns to the account that initialized the resolution.
+    pub fn close_resolution_state(ctx: Context<CloseResolutionState>) -> Result<()> {
+        let resolution_state = &ctx.accounts.resolution_state;
+
+        require!(
+            resolution_state.is_finalized,
+            ResolutionError::ResolutionNotFinalized
+        );
+        require!(
+            resolution_state.closed_vote_records == resolution_state.total_voters,
+            ResolutionError::OpenVoteRecords
+        );
+
+        msg!("Closed resolution state for market {}", resolution_state.market_id);
+
+        Ok(())
+    }
 }
 
 // ==============================================================================
 // Helper Functions
 // ==============================================================================
 
+impl<'info> CoreMarketsSettlement<'info> {
+    /// Apply a final outcome to the CoreMarkets market via CPI
+    ///
+    /// Yes/No/Outcome(index) resolve the market with resolve_market,
+    /// ScalarValue resolves a scalar market with resolve_scalar_market, and
+    /// Cancel cancels it so bettors can claim refunds. CoreMarkets rejects an
+    /// outcome that does not fit the market kind.
+    fn settle_market(&self, market_id: u64, outcome: &VoteChoice, resolver_bump: u8) -> Result<()> {
+        // Validate the market account belongs to this resolution
+        let (expected_market, _) = Pubkey::find_program_address(
+            &[b"market", market_id.to_le_bytes().as_ref()],
+            &self.core_markets_program.key(),
+        );
+        require!(
+            self.market.key() == expected_market,
+            ResolutionError::MarketMismatch
+        );
+
+        let signer_seeds: &[&[&[u8]]] = &[&[RESOLVER_AUTHORITY_SEED, &[resolver_bump]]];
+        let resolve_ctx = || {
+            CpiContext::new_with_signer(
+                self.core_markets_program.to_account_info(),
+                self.resolve_market_accounts(),
+                signer_seeds,
+            )
+        };
+
+        match outcome {
+            VoteChoice::Yes => core_markets::cpi::resolve_market(resolve_ctx(), BetSide::Yes),
+            VoteChoice::No => core_markets::cpi::resolve_market(resolve_ctx(), BetSide::No),
+            VoteChoice::Outcome(index) => {
+                core_markets::cpi::resolve_market(resolve_ctx(), BetSide::Outcome(*index))
+            }
+            VoteChoice::ScalarValue(value) => {
+                core_markets::cpi::resolve_scalar_market(resolve_ctx(), *value)
+            }
+            VoteChoice::Cancel => core_markets::cpi::cancel_market(CpiContext::new_with_signer(
+                self.core_markets_program.to_account_info(),
+                core_markets::cpi::accounts::CancelMarket {
+                    market: self.market.to_account_info(),
+                    global_parameters: self.global_parameters.to_account_info(),
+                    authority: self.resolver_authority.to_account_info(),
+                    parameter_storage_program: self.parameter_storage_program.to_account_info(),
+                    series: self.series.as_ref().map(|a| a.to_account_info()),
+                },
+                signer_seeds,
+            )),
+        }
+    }
+
+    /// Accounts for the resolve_market / resolve_scalar_market CPIs
+    fn resolve_market_accounts(&self) -> core_markets::cpi::accounts::ResolveMarket<'info> {
+        core_markets::cpi::accounts::ResolveMarket {
+            market: self.market.to_account_info(),
+            global_parameters: self.global_parameters.to_account_info(),
+            global_toggles: self.global_toggles.to_account_info(),
+            platform_wallet: self.platform_wallet.to_account_info(),
+            creator_wallet: self.creator_wallet.to_account_info(),
+            treasury: self.treasury.to_account_info(),
+            authority: self.resolver_authority.to_account_info(),
+            parameter_storage_program: self.parameter_storage_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            collateral_mint: self.collateral_mint.as_ref().map(|a| a.to_account_info()),
+            market_vault: self.market_vault.as_ref().map(|a| a.to_account_info()),
+            platform_token_account: self
+                .platform_token_account
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            creator_token_account: self
+                .creator_token_account
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            treasury_token_account: self
+                .treasury_token_account
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            token_program: self.token_program.as_ref().map(|a| a.to_account_info()),
+            series: self.series.as_ref().map(|a| a.to_account_info()),
+        }
+    }
+}
+
+/// Reject when the emergency pause is set or resolution is switched off
+fn require_resolution_enabled(global_toggles: &AccountInfo) -> Result<()> {
+    let parameter_storage_program = Pubkey::from_str(PARAMETER_STORAGE_PROGRAM_ID_STR)
+        .map_err(|_| ResolutionError::InvalidFeatureToggles)?;
+    require!(
+        *global_toggles.owner == parameter_storage_program,
+        ResolutionError::InvalidFeatureToggles
+    );
+
+    let toggles_data = global_toggles.try_borrow_data()?;
+    let toggles = GlobalFeatureToggles::try_deserialize(&mut &toggles_data[..])?;
+
+    require!(!toggles.emergency_pause, ResolutionError::EmergencyPaused);
+    require!(toggles.resolution_enabled, ResolutionError::ResolutionDisabled);
+    Ok(())
+}
+
 /// Determine outcome by majority vote
 fn determine_outcome(yes_votes: u64, no_votes: u64, cancel_votes: u64) -> VoteChoice {
     if cancel_votes > yes_votes && cancel_votes > no_votes {
@@ -306,6 +497,8 @@ pub struct ResolutionState {
     pub voting_started_at: i64,
     pub dispute_window_ends_at: i64,
     pub finalized_at: Option<i64>,
+    pub payer: Pubkey,              // Initializer, refunded on close
+    pub closed_vote_records: u32,
     pub bump: u8,
 }
 
@@ -316,7 +509,7 @@ pub struct ResolutionState {
 #[account]
 pub struct VoteResult {
     pub market_id: u64,           // Reference to market
-    pub outcome: VoteChoice,       // Final outcome (YES/NO/TIE)
+    pub outcome: VoteChoice,       // Final outcome (YES/NO/TIE, categorical index or scalar value)
     pub yes_vote_weight: u64,      // Total YES vote weight
     pub no_vote_weight: u64,       // Total NO vote weight
     pub total_votes_count: u32,    // Number of unique voters
@@ -330,7 +523,7 @@ pub struct VoteResult {
 impl VoteResult {
     pub const LEN: usize = 8 + // discriminator
         8 + // market_id
-        1 + // outcome (enum)
+        1 + 8 + // outcome (VoteChoice, up to a scalar value)
         8 + // yes_vote_weight
         8 + // no_vote_weight
         4 + // total_votes_count
@@ -339,6 +532,11 @@ impl VoteResult {
         32 + // posted_by
         8 + // dispute_window_end
         1; // bump
+
+    /// Whether `leaf` is included under merkle_root (sorted-pair keccak256 tree)
+    pub fn verify_inclusion(&self, leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+        core_markets::verify_merkle_proof(proof, &self.merkle_root, leaf)
+    }
 }
 
 // ==============================================================================
@@ -365,6 +563,8 @@ pub enum VoteChoice {
     Yes,
     No,
     Cancel,
+    Outcome(u8),       // Categorical markets: winning index into outcome_labels
+    ScalarValue(i64),  // Scalar markets: observed value (clamped to the market bounds)
 }
 
 // ==============================================================================
@@ -393,10 +593,21 @@ pub struct SubmitVote<'info> {
     )]
     pub resolution_state: Account<'info, ResolutionState>,
 
+    /// CHECK: Feature toggles from ParameterStorage - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
     #[account(mut)]
     pub voter: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -405,7 +616,7 @@ pub struct InitializeResolution<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 8 + 8*3 + 4 + 1 + 33 + 8*2 + 9 + 1, // ~100 bytes
+        space = 8 + 8 + 8*3 + 4 + 1 + 33 + 8*2 + 9 + 32 + 4 + 1, // ~140 bytes
         seeds = [b"resolution-state", market_id.to_le_bytes().as_ref()],
         bump
     )]
@@ -419,6 +630,14 @@ pub struct InitializeResolution<'info> {
     )]
     pub global_parameters: Account<'info, GlobalParameters>,
 
+    /// CHECK: Feature toggles from ParameterStorage - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -437,9 +656,74 @@ pub struct FinalizeResolution<'info> {
     )]
     pub resolution_state: Account<'info, ResolutionState>,
 
+    pub core_markets: CoreMarketsSettlement<'info>,
+
     pub authority: Signer<'info>,
 }
 
+/// Accounts needed to resolve or cancel a CoreMarkets market via CPI
+#[derive(Accounts)]
+pub struct CoreMarketsSettlement<'info> {
+    /// CHECK: CoreMarkets market PDA - validated against resolution_state.market_id
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    /// CHECK: Global parameters from ParameterStorage - validated by CoreMarkets
+    pub global_parameters: UncheckedAccount<'info>,
+
+    /// CHECK: Feature toggles from ParameterStorage - owner checked here and by CoreMarkets
+    pub global_toggles: UncheckedAccount<'info>,
+
+    /// CHECK: Platform wallet - validated by CoreMarkets against GlobalParameters.authority
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Market creator wallet - validated by CoreMarkets against market.creator
+    #[account(mut)]
+    pub creator_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: CoreMarkets treasury PDA - validated by CoreMarkets seeds
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Resolver PDA, signs CoreMarkets resolve/cancel CPIs
+    #[account(seeds = [RESOLVER_AUTHORITY_SEED], bump)]
+    pub resolver_authority: UncheckedAccount<'info>,
+
+    pub core_markets_program: Program<'info, CoreMarkets>,
+
+    /// CHECK: ParameterStorage program ID - validated by CoreMarkets seeds
+    pub parameter_storage_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: SPL collateral mint - validated by CoreMarkets (omit for SOL markets)
+    pub collateral_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Market token vault - validated by CoreMarkets
+    #[account(mut)]
+    pub market_vault: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Platform fee token account - validated by CoreMarkets
+    #[account(mut)]
+    pub platform_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Creator fee token account - validated by CoreMarkets
+    #[account(mut)]
+    pub creator_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Treasury fee token account - validated by CoreMarkets
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: SPL Token or Token-2022 program - validated by CoreMarkets
+    pub token_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: MarketSeries of a series market - validated by CoreMarkets (omit otherwise)
+    #[account(mut)]
+    pub series: Option<UncheckedAccount<'info>>,
+}
+
 #[derive(Accounts)]
 pub struct AdminOverrideResolution<'info> {
     #[account(
@@ -457,6 +741,8 @@ pub struct AdminOverrideResolution<'info> {
     )]
     pub global_parameters: Account<'info, GlobalParameters>,
 
+    pub core_markets: CoreMarketsSettlement<'info>,
+
     pub admin: Signer<'info>,
 
     /// CHECK: ParameterStorage program ID
@@ -484,6 +770,14 @@ pub struct PostVoteResult<'info> {
     )]
     pub global_parameters: Account<'info, GlobalParameters>,
 
+    /// CHECK: Feature toggles from ParameterStorage - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
     /// Platform admin authority (posts aggregated results)
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -494,10 +788,64 @@ pub struct PostVoteResult<'info> {
     pub parameter_storage_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CloseVoteRecord<'info> {
+    #[account(
+        mut,
+        close = voter,
+        seeds = [
+            b"vote-record",
+            resolution_state.market_id.to_le_bytes().as_ref(),
+            voter.key().as_ref()
+        ],
+        bump = vote_record.bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"resolution-state", resolution_state.market_id.to_le_bytes().as_ref()],
+        bump = resolution_state.bump
+    )]
+    pub resolution_state: Account<'info, ResolutionState>,
+
+    /// CHECK: Voter who paid the rent - bound by the vote_record seeds
+    #[account(mut)]
+    pub voter: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseResolutionState<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"resolution-state", resolution_state.market_id.to_le_bytes().as_ref()],
+        bump = resolution_state.bump
+    )]
+    pub resolution_state: Account<'info, ResolutionState>,
+
+    /// CHECK: Rent payer recorded in resolution_state
+    #[account(mut, address = resolution_state.payer @ ResolutionError::Unauthorized)]
+    pub payer: UncheckedAccount<'info>,
+}
+
 // ==============================================================================
 // External Account Structures (from ParameterStorage)
 // ==============================================================================
 
+#[account]
+pub struct GlobalFeatureToggles {
+    pub authority: Pubkey,
+    pub market_creation_enabled: bool,
+    pub betting_enabled: bool,
+    pub resolution_enabled: bool,
+    pub proposals_enabled: bool,
+    pub emergency_pause: bool,
+    pub last_updated: i64,
+    pub version: u32,
+    pub bump: u8,
+}
+
 #[account]
 pub struct GlobalParameters {
     pub authority: Pubkey,
@@ -586,4 +934,25 @@ pub enum ResolutionError {
 
     #[msg("Unauthorized: only admin can perform this action")]
     Unauthorized,
+
+    #[msg("Market account does not match the resolution market_id")]
+    MarketMismatch,
+
+    #[msg("Invalid feature toggles account: must be the ParameterStorage global-toggles PDA")]
+    InvalidFeatureToggles,
+
+    #[msg("Protocol is under emergency pause")]
+    EmergencyPaused,
+
+    #[msg("Market resolution is disabled")]
+    ResolutionDisabled,
+
+    #[msg("Market resolution is not finalized yet")]
+    ResolutionNotFinalized,
+
+    #[msg("Vote records must be closed before the resolution state")]
+    OpenVoteRecords,
+
+    #[msg("Only Yes, No or Cancel can be voted on-chain")]
+    InvalidVoteChoice,
 }
@@ -2,6 +2,17 @@ use anchor_lang::prelude::*;
 
 declare_id!("J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD");
 
+/// Bounds on claim_period_seconds (winners must get a real window to claim)
+pub const MIN_CLAIM_PERIOD_SECONDS: u64 = 604_800; // 7 days
+pub const MAX_CLAIM_PERIOD_SECONDS: u64 = 157_680_000; // 5 years
+
+/// Upper bound on betting_cutoff_seconds; it must also stay below min_duration_seconds
+pub const MAX_BETTING_CUTOFF_SECONDS: u64 = 86_400; // 24 hours
+
+/// Bounds on early_resolution_challenge_seconds
+pub const MIN_EARLY_RESOLUTION_CHALLENGE_SECONDS: u64 = 3_600; // 1 hour
+pub const MAX_EARLY_RESOLUTION_CHALLENGE_SECONDS: u64 = 604_800; // 7 days
+
 /// BMAD-Zmart Parameter Storage
 ///
 /// Global configuration management with safety constraints.
@@ -12,6 +23,10 @@ declare_id!("J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD");
 /// - 24-hour cooldown between updates
 /// - Maximum 20% change per update
 /// - Event emission for audit trail
+///
+/// GlobalParameters fields added after the first deployment are appended to
+/// the end of the account; migrate_parameters grows an existing account and
+/// fills them with their defaults.
 #[program]
 pub mod parameter_storage {
     use super::*;
@@ -65,6 +80,9 @@ pub mod parameter_storage {
         params.medium_tier_fee_bps = 100; // 1.0% for bonds 100-499 ZMart
         params.high_tier_fee_bps = 200;   // 2.0% for bonds ≥500 ZMart
 
+        // Fields appended after the original layout
+        params.set_appended_defaults();
+
         // Safety constraints
         params.update_cooldown_seconds = 86_400; // 24 hours
         params.max_change_bps = 2000;            // 20%
@@ -92,7 +110,8 @@ pub mod parameter_storage {
 
     /// Update a numeric parameter with safety checks
     ///
-    /// Enforces cooldown period and maximum change percentage.
+    /// Enforces cooldown period and maximum change percentage (except for
+    /// mode switches, see ParameterType::is_mode).
     /// Emits event for audit trail.
     pub fn update_parameter(
         ctx: Context<UpdateParameter>,
@@ -110,7 +129,7 @@ pub mod parameter_storage {
 
         // Get current value and validate change
         let current_value = params.get_parameter_value(&param_type);
-        validate_parameter_change(current_value, new_value, params.max_change_bps)?;
+        validate_parameter_change(&param_type, current_value, new_value, params.max_change_bps)?;
 
         // Update parameter
         params.set_parameter_value(&param_type, new_value)?;
@@ -165,6 +184,73 @@ pub mod parameter_storage {
         msg!("Toggle {:?} updated from {} to {}", toggle_type, old_value, enabled);
         Ok(())
     }
+
+    /// Grow a GlobalParameters account created with an older layout
+    ///
+    /// Authority-only. Reallocates to GlobalParameters::LEN (authority pays the
+    /// extra rent) and sets the appended fields to their defaults; existing
+    /// values are untouched.
+    pub fn migrate_parameters(ctx: Context<MigrateParameters>) -> Result<()> {
+        let parameters = &ctx.accounts.parameters;
+        require!(
+            parameters.owner == &crate::ID,
+            ParameterError::InvalidAccount
+        );
+
+        let old_len = parameters.data_len();
+        require!(
+            (GlobalParameters::V1_LEN..GlobalParameters::LEN).contains(&old_len),
+            ParameterError::AlreadyMigrated
+        );
+
+        // Authority is the first field after the discriminator in every layout
+        {
+            let data = parameters.try_borrow_data()?;
+            require!(
+                data[..8] == *GlobalParameters::DISCRIMINATOR,
+                ParameterError::InvalidAccount
+            );
+            let authority = Pubkey::try_from(&data[8..40]).map_err(|_| ParameterError::InvalidAccount)?;
+            require!(
+                authority == ctx.accounts.authority.key(),
+                ParameterError::Unauthorized
+            );
+        }
+
+        // Top up rent for the new size, then grow (new bytes are zeroed)
+        let rent = Rent::get()?;
+        let shortfall = rent
+            .minimum_balance(GlobalParameters::LEN)
+            .saturating_sub(parameters.lamports());
+        if shortfall > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: parameters.to_account_info(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        parameters.resize(GlobalParameters::LEN)?;
+
+        let mut params = {
+            let data = parameters.try_borrow_data()?;
+            GlobalParameters::try_deserialize(&mut &data[..])?
+        };
+        params.set_appended_defaults();
+        params.last_updated = Clock::get()?.unix_timestamp;
+        params.try_serialize(&mut &mut parameters.try_borrow_mut_data()?[..])?;
+
+        msg!(
+            "GlobalParameters migrated from {} to {} bytes",
+            old_len,
+            GlobalParameters::LEN
+        );
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -222,6 +308,38 @@ pub struct GlobalParameters {
     pub cooldown_until: i64,
     pub version: u32,
     pub bump: u8,
+
+    // ------------------------------------------------------------------------
+    // Appended after the original layout so deployed accounts keep their
+    // offsets; older accounts are grown by migrate_parameters
+    // ------------------------------------------------------------------------
+
+    // Position exit fee (sell_position) - in basis points
+    pub exit_fee_bps: u16, // 1% = 100 (default)
+
+    // Resolution with an empty winning side: 0 = REFUND_BETTORS, 1 = SWEEP_TO_PLATFORM
+    pub no_winner_policy: u8,
+
+    // Protocol treasury share of each bet - in basis points
+    pub treasury_fee_bps: u16, // 0 = disabled (default)
+
+    // Referrer share of the platform fee on referred bets - in basis points of the fee
+    pub referral_fee_bps: u16, // 20% = 2000 (default), capped at 50%
+
+    // Claim period after settlement; core_markets::close_market sweeps what is left
+    pub claim_period_seconds: i64, // 90 days (default)
+
+    // Betting closes this long before a market's end_date (anti-sniping)
+    pub betting_cutoff_seconds: i64, // 5 minutes (default)
+
+    // Challenge period before a proposed early resolution can be finalized
+    pub early_resolution_challenge_seconds: i64, // 24 hours (default)
+
+    // Cumulative stake one wallet may place on a single market (anti-whale)
+    pub max_position_per_wallet_lamports: u64, // 200 SOL (default)
+
+    // Bond posted to challenge an early resolution (forfeited if it stands)
+    pub early_resolution_challenge_bond_lamports: u64, // 1 SOL (default)
 }
 
 /// Global feature toggles
@@ -269,6 +387,15 @@ pub enum ParameterType {
     LowTierFee, // Story 2.11: Creator fee % for low bond tier <100 ZMart (basis points)
     MediumTierFee, // Story 2.11: Creator fee % for medium bond tier 100-499 ZMart (basis points)
     HighTierFee, // Story 2.11: Creator fee % for high bond tier ≥500 ZMart (basis points)
+    ExitFee, // Fee % charged when selling a position before market end (basis points)
+    NoWinnerPolicy, // 0 = REFUND_BETTORS, 1 = SWEEP_TO_PLATFORM
+    TreasuryFee, // Treasury fee % accrued on each bet (basis points)
+    ReferralFee, // Referrer share of the platform fee (basis points of the fee, max 5000)
+    ClaimPeriod, // Seconds after settlement before a market can be closed
+    BettingCutoff, // Seconds before end_date when betting closes
+    EarlyResolutionChallenge, // Seconds a proposed early resolution can be challenged
+    MaxPositionPerWallet, // Cumulative stake per wallet per market (lamports)
+    EarlyResolutionChallengeBond, // Bond to challenge an early resolution (lamports)
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
@@ -285,6 +412,54 @@ pub enum ToggleType {
 // ============================================================================
 
 impl GlobalParameters {
+    /// Account size of the original layout (before the appended fields)
+    pub const V1_LEN: usize = 8 + 32 + 8*9 + 2*2 + 8*3 + 8 + 1 + 2*3 + 2*3 + 8 + 2 + 8*2 + 4 + 1; // 192 bytes
+
+    /// Current account size
+    pub const LEN: usize = Self::V1_LEN
+        + 2 // exit_fee_bps
+        + 1 // no_winner_policy
+        + 2 // treasury_fee_bps
+        + 2 // referral_fee_bps
+        + 8 // claim_period_seconds
+        + 8 // betting_cutoff_seconds
+        + 8 // early_resolution_challenge_seconds
+        + 8 // max_position_per_wallet_lamports
+        + 8; // early_resolution_challenge_bond_lamports
+
+    /// Defaults for the fields appended after the original layout
+    ///
+    /// Used by initialize_parameters and by migrate_parameters for accounts
+    /// created before those fields existed.
+    pub fn set_appended_defaults(&mut self) {
+        // Position exit fee charged by core_markets::sell_position - in basis points
+        self.exit_fee_bps = 100; // 1%
+
+        // Resolution with an empty winning side: 0 = REFUND_BETTORS, 1 = SWEEP_TO_PLATFORM
+        self.no_winner_policy = 0; // Default: REFUND_BETTORS
+
+        // Protocol treasury share of each bet - in basis points
+        self.treasury_fee_bps = 0; // Disabled until set by governance
+
+        // Referrer share of the platform fee on referred bets - in basis points of the fee
+        self.referral_fee_bps = 2000; // 20% of the platform fee
+
+        // Claim period after settlement; core_markets::close_market sweeps what is left
+        self.claim_period_seconds = 7_776_000; // 90 days
+
+        // Betting closes this long before a market's end_date (anti-sniping)
+        self.betting_cutoff_seconds = 300; // 5 minutes
+
+        // Challenge period before a proposed early resolution can be finalized
+        self.early_resolution_challenge_seconds = 86_400; // 24 hours
+
+        // Cumulative stake one wallet may place on a single market (anti-whale)
+        self.max_position_per_wallet_lamports = 200_000_000_000; // 200 SOL
+
+        // Bond posted by an early resolution challenger, forfeited if the proposal stands
+        self.early_resolution_challenge_bond_lamports = 1_000_000_000; // 1 SOL
+    }
+
     pub fn get_parameter_value(&self, param_type: &ParameterType) -> u64 {
         match param_type {
             ParameterType::CreationBond => self.creation_bond_lamports,
@@ -307,6 +482,15 @@ impl GlobalParameters {
             ParameterType::LowTierFee => self.low_tier_fee_bps as u64,
             ParameterType::MediumTierFee => self.medium_tier_fee_bps as u64,
             ParameterType::HighTierFee => self.high_tier_fee_bps as u64,
+            ParameterType::ExitFee => self.exit_fee_bps as u64,
+            ParameterType::NoWinnerPolicy => self.no_winner_policy as u64,
+            ParameterType::TreasuryFee => self.treasury_fee_bps as u64,
+            ParameterType::ReferralFee => self.referral_fee_bps as u64,
+            ParameterType::ClaimPeriod => self.claim_period_seconds as u64,
+            ParameterType::BettingCutoff => self.betting_cutoff_seconds as u64,
+            ParameterType::EarlyResolutionChallenge => self.early_resolution_challenge_seconds as u64,
+            ParameterType::MaxPositionPerWallet => self.max_position_per_wallet_lamports,
+            ParameterType::EarlyResolutionChallengeBond => self.early_resolution_challenge_bond_lamports,
         }
     }
 
@@ -324,7 +508,14 @@ impl GlobalParameters {
             ParameterType::MinBet => self.min_bet_lamports = value,
             ParameterType::MaxBet => self.max_bet_lamports = value,
             ParameterType::MaxMarketSize => self.max_market_size_lamports = value,
-            ParameterType::MinDuration => self.min_duration_seconds = value as i64,
+            ParameterType::MinDuration => {
+                // Even the shortest market must take bets before the cutoff
+                require!(
+                    value > self.betting_cutoff_seconds as u64,
+                    ParameterError::InvalidValue
+                );
+                self.min_duration_seconds = value as i64;
+            }
             ParameterType::MaxDuration => self.max_duration_seconds = value as i64,
             ParameterType::DisputeWindow => self.dispute_window_seconds = value as i64,
             ParameterType::BondTier1 => self.bond_tier_1_lamports = value,
@@ -359,9 +550,99 @@ impl GlobalParameters {
                 require!(value <= 10000, ParameterError::InvalidValue);
                 self.high_tier_fee_bps = value as u16;
             }
+            ParameterType::ExitFee => {
+                require!(value <= 10000, ParameterError::InvalidValue);
+                self.exit_fee_bps = value as u16;
+            }
+            ParameterType::NoWinnerPolicy => {
+                require!(value <= 1, ParameterError::InvalidValue);
+                self.no_winner_policy = value as u8;
+            }
+            ParameterType::TreasuryFee => {
+                require!(value <= 10000, ParameterError::InvalidValue);
+                self.treasury_fee_bps = value as u16;
+            }
+            ParameterType::ReferralFee => {
+                require!(value <= 5000, ParameterError::InvalidValue);
+                self.referral_fee_bps = value as u16;
+            }
+            ParameterType::ClaimPeriod => {
+                require!(
+                    (MIN_CLAIM_PERIOD_SECONDS..=MAX_CLAIM_PERIOD_SECONDS).contains(&value),
+                    ParameterError::InvalidValue
+                );
+                self.claim_period_seconds = value as i64;
+            }
+            ParameterType::BettingCutoff => {
+                require!(
+                    value <= MAX_BETTING_CUTOFF_SECONDS
+                        && value < self.min_duration_seconds as u64,
+                    ParameterError::InvalidValue
+                );
+                self.betting_cutoff_seconds = value as i64;
+            }
+            ParameterType::EarlyResolutionChallenge => {
+                require!(
+                    (MIN_EARLY_RESOLUTION_CHALLENGE_SECONDS..=MAX_EARLY_RESOLUTION_CHALLENGE_SECONDS)
+                        .contains(&value),
+                    ParameterError::InvalidValue
+                );
+                self.early_resolution_challenge_seconds = value as i64;
+            }
+            ParameterType::MaxPositionPerWallet => {
+                require!(value > 0, ParameterError::InvalidValue);
+                self.max_position_per_wallet_lamports = value;
+            }
+            ParameterType::EarlyResolutionChallengeBond => {
+                require!(value > 0, ParameterError::InvalidValue);
+                self.early_resolution_challenge_bond_lamports = value;
+            }
+        }
+
+        // Fees are all taken from the same bet: they must fit in 100% together
+        if param_type.is_bet_fee() {
+            require!(
+                self.max_total_fee_bps() <= 10000,
+                ParameterError::FeesExceedTotal
+            );
         }
         Ok(())
     }
+
+    /// Largest combined platform + creator + treasury fee a bet can be charged
+    ///
+    /// The creator fee is whichever of the global and tiered creator fees is highest.
+    pub fn max_total_fee_bps(&self) -> u64 {
+        let creator_fee_bps = self
+            .creator_fee_bps
+            .max(self.low_tier_fee_bps)
+            .max(self.medium_tier_fee_bps)
+            .max(self.high_tier_fee_bps);
+        self.platform_fee_bps as u64 + creator_fee_bps as u64 + self.treasury_fee_bps as u64
+    }
+}
+
+impl ParameterType {
+    /// Fees deducted from every bet (checked together by max_total_fee_bps)
+    pub fn is_bet_fee(&self) -> bool {
+        matches!(
+            self,
+            ParameterType::PlatformFee
+                | ParameterType::CreatorFee
+                | ParameterType::LowTierFee
+                | ParameterType::MediumTierFee
+                | ParameterType::HighTierFee
+                | ParameterType::TreasuryFee
+        )
+    }
+
+    /// Enum-like settings where a percentage change is meaningless (0 <-> 1)
+    pub fn is_mode(&self) -> bool {
+        matches!(
+            self,
+            ParameterType::VotingWeightMode | ParameterType::NoWinnerPolicy
+        )
+    }
 }
 
 impl GlobalFeatureToggles {
@@ -391,17 +672,19 @@ impl GlobalFeatureToggles {
 // Validation Functions
 // ============================================================================
 
-fn validate_parameter_change(old_value: u64, new_value: u64, max_change_bps: u16) -> Result<()> {
-    if old_value == 0 {
-        // Allow any change from 0
+fn validate_parameter_change(
+    param_type: &ParameterType,
+    old_value: u64,
+    new_value: u64,
+    max_change_bps: u16,
+) -> Result<()> {
+    if old_value == 0 || param_type.is_mode() {
+        // Allow any change from 0, and any switch between modes
+        // (set_parameter_value checks the value is a valid mode)
         return Ok(());
     }
 
-    let diff = if new_value > old_value {
-        new_value - old_value
-    } else {
-        old_value - new_value
-    };
+    let diff = new_value.abs_diff(old_value);
 
     let max_allowed_change = (old_value as u128 * max_change_bps as u128) / 10000;
 
@@ -422,7 +705,7 @@ pub struct InitializeParameters<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8*9 + 2*2 + 8*3 + 8 + 1 + 2*3 + 2*3 + 8 + 2 + 8*2 + 4 + 1, // ~229 bytes (Story 2.10: +6 for refund %, Story 2.11: +6 for tier fee %)
+        space = GlobalParameters::LEN, // 239 bytes
         seeds = [b"global-parameters"],
         bump
     )]
@@ -456,6 +739,23 @@ pub struct UpdateParameter<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateParameters<'info> {
+    /// CHECK: Raw account (older layouts don't deserialize) - validated via seeds,
+    /// owner and discriminator in the handler
+    #[account(
+        mut,
+        seeds = [b"global-parameters"],
+        bump
+    )]
+    pub parameters: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateToggle<'info> {
     #[account(
@@ -508,4 +808,60 @@ pub enum ParameterError {
 
     #[msg("Invalid parameter value")]
     InvalidValue,
+
+    #[msg("Platform, creator and treasury fees together exceed 10000 bps")]
+    FeesExceedTotal,
+
+    #[msg("Parameters account already uses the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Not a GlobalParameters account owned by this program")]
+    InvalidAccount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An account with every field zeroed
+    fn zeroed<T: AccountDeserialize + Discriminator>() -> T {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.resize(GlobalParameters::LEN, 0);
+        T::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    /// update_parameter's validation and write, without the cooldown
+    fn update(params: &mut GlobalParameters, param_type: ParameterType, value: u64) -> Result<()> {
+        let current_value = params.get_parameter_value(&param_type);
+        validate_parameter_change(&param_type, current_value, value, params.max_change_bps)?;
+        params.set_parameter_value(&param_type, value)
+    }
+
+    #[test]
+    fn mode_parameters_toggle_both_ways() {
+        let mut params: GlobalParameters = zeroed();
+        params.max_change_bps = 2000;
+
+        for mode in [ParameterType::NoWinnerPolicy, ParameterType::VotingWeightMode] {
+            update(&mut params, mode.clone(), 1).unwrap();
+            assert_eq!(params.get_parameter_value(&mode), 1);
+            update(&mut params, mode.clone(), 0).unwrap();
+            assert_eq!(params.get_parameter_value(&mode), 0);
+            update(&mut params, mode.clone(), 1).unwrap();
+            assert_eq!(params.get_parameter_value(&mode), 1);
+            assert!(update(&mut params, mode, 2).is_err());
+        }
+    }
+
+    #[test]
+    fn numeric_parameters_stay_limited_to_max_change() {
+        let mut params: GlobalParameters = zeroed();
+        params.max_change_bps = 2000;
+        params.min_bet_lamports = 1_000;
+
+        assert!(update(&mut params, ParameterType::MinBet, 0).is_err());
+        assert!(update(&mut params, ParameterType::MinBet, 1_201).is_err());
+        update(&mut params, ParameterType::MinBet, 1_200).unwrap();
+        assert_eq!(params.min_bet_lamports, 1_200);
+    }
 }
@@ -1,13 +1,24 @@
 use anchor_lang::prelude::*;
+use std::str::FromStr;
 
 declare_id!("5XH5i8dypiB4Wwa7TkmU6dnk9SyUGqE92GiQMHypPekL");
 
+/// Parameter Storage Program ID string (owner of the feature toggles PDA)
+const PARAMETER_STORAGE_PROGRAM_ID_STR: &str = "J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD";
+
+/// Market metadata limits (tag count, bytes) - same as CoreMarkets
+pub const MAX_TAGS: usize = 5;
+pub const MAX_TAG_LEN: usize = 32;
+pub const MAX_RESOLUTION_SOURCE_LEN: usize = 200;
+
 /// BMAD-Zmart Proposal System
 ///
 /// Governance system for community-driven market creation.
 ///
 /// Architecture:
 /// - Proposal PDA per proposal: ["proposal", proposal_id]
+/// - ProposalMetadata per proposal: ["proposal-metadata", proposal_id], carried
+///   passed on to CoreMarkets create_market when the approved market is created
 /// - Bond requirement with 1% non-refundable tax
 /// - Graduated bond tiers determine creator fee percentage
 /// - Voting with ≥60% YES threshold for approval
@@ -19,6 +30,8 @@ declare_id!("5XH5i8dypiB4Wwa7TkmU6dnk9SyUGqE92GiQMHypPekL");
 /// - One vote per wallet per proposal
 /// - Approval threshold enforcement (≥60%)
 /// - Admin override for MVP progressive decentralization
+/// - Proposal creation and voting honour the proposals toggle and emergency pause
+/// - Vote records close once the proposal is processed (rent to the voter)
 #[program]
 pub mod proposal_system {
     use super::*;
@@ -27,6 +40,7 @@ pub mod proposal_system {
     ///
     /// Requires bond deposit based on tier selection.
     /// 1% of bond is non-refundable (proposal tax).
+    /// `metadata` is stored in the proposal's ProposalMetadata PDA.
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         proposal_id: u64,
@@ -34,7 +48,10 @@ pub mod proposal_system {
         description: String,
         bond_tier: BondTier,
         end_date: i64,
+        metadata: MarketMetadataArgs,
     ) -> Result<()> {
+        require_proposals_enabled(&ctx.accounts.global_toggles)?;
+
         let params = &ctx.accounts.global_parameters;
         let clock = Clock::get()?;
 
@@ -51,6 +68,18 @@ pub mod proposal_system {
             end_date > clock.unix_timestamp,
             ProposalError::InvalidEndDate
         );
+        require!(
+            metadata.tags.len() <= MAX_TAGS
+                && metadata
+                    .tags
+                    .iter()
+                    .all(|tag| !tag.is_empty() && tag.len() <= MAX_TAG_LEN),
+            ProposalError::InvalidTags
+        );
+        require!(
+            metadata.resolution_source.len() <= MAX_RESOLUTION_SOURCE_LEN,
+            ProposalError::InvalidResolutionSource
+        );
 
         // Determine bond amount based on tier
         let bond_amount = match bond_tier {
@@ -94,6 +123,15 @@ pub mod proposal_system {
         proposal.market_id = None;
         proposal.bump = ctx.bumps.proposal;
 
+        // Off-chain metadata anchor
+        let proposal_metadata = &mut ctx.accounts.proposal_metadata;
+        proposal_metadata.proposal_id = proposal_id;
+        proposal_metadata.category = metadata.category.clone();
+        proposal_metadata.tags = metadata.tags;
+        proposal_metadata.resolution_source = metadata.resolution_source;
+        proposal_metadata.metadata_hash = metadata.metadata_hash;
+        proposal_metadata.bump = ctx.bumps.proposal_metadata;
+
         let bond_tier_copy = bond_tier.clone();
 
         emit!(ProposalCreatedEvent {
@@ -104,6 +142,8 @@ pub mod proposal_system {
             bond_tier,
             proposal_tax,
             end_date,
+            category: metadata.category,
+            metadata_hash: metadata.metadata_hash,
             timestamp: clock.unix_timestamp,
         });
 
@@ -126,6 +166,8 @@ pub mod proposal_system {
         ctx: Context<VoteOnProposal>,
         vote_choice: VoteChoice,
     ) -> Result<()> {
+        require_proposals_enabled(&ctx.accounts.global_toggles)?;
+
         let proposal = &mut ctx.accounts.proposal;
         let vote_record = &mut ctx.accounts.vote_record;
         let clock = Clock::get()?;
@@ -304,6 +346,45 @@ pub mod proposal_system {
 
         Ok(())
     }
+
+    /// Close a vote record once its proposal is approved or rejected
+    ///
+    /// Permissionless; rent returns to the voter.
+    pub fn close_vote_record(ctx: Context<CloseProposalVoteRecord>) -> Result<()> {
+        require!(
+            ctx.accounts.proposal.status != ProposalStatus::Pending,
+            ProposalError::ProposalNotProcessed
+        );
+
+        msg!(
+            "Closed vote record of {} for proposal {}",
+            ctx.accounts.voter.key(),
+            ctx.accounts.proposal.proposal_id
+        );
+
+        Ok(())
+    }
+}
+
+// ==============================================================================
+// Helper Functions
+// ==============================================================================
+
+/// Reject when the emergency pause is set or proposals are switched off
+fn require_proposals_enabled(global_toggles: &AccountInfo) -> Result<()> {
+    let parameter_storage_program = Pubkey::from_str(PARAMETER_STORAGE_PROGRAM_ID_STR)
+        .map_err(|_| ProposalError::InvalidFeatureToggles)?;
+    require!(
+        *global_toggles.owner == parameter_storage_program,
+        ProposalError::InvalidFeatureToggles
+    );
+
+    let toggles_data = global_toggles.try_borrow_data()?;
+    let toggles = GlobalFeatureToggles::try_deserialize(&mut &toggles_data[..])?;
+
+    require!(!toggles.emergency_pause, ProposalError::EmergencyPaused);
+    require!(toggles.proposals_enabled, ProposalError::ProposalsDisabled);
+    Ok(())
 }
 
 // ==============================================================================
@@ -331,6 +412,39 @@ pub struct Proposal {
     pub bump: u8,
 }
 
+/// Proposed market metadata: ["proposal-metadata", proposal_id]
+///
+/// Same fields as CoreMarkets MarketMetadata; metadata_hash anchors the
+/// off-chain metadata JSON.
+#[account]
+pub struct ProposalMetadata {
+    pub proposal_id: u64,
+    pub category: MarketCategory,
+    pub tags: Vec<String>,
+    pub resolution_source: String,
+    pub metadata_hash: [u8; 32],
+    pub bump: u8,
+}
+
+impl ProposalMetadata {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // proposal_id
+        1 + // category
+        4 + MAX_TAGS * (4 + MAX_TAG_LEN) + // tags
+        4 + MAX_RESOLUTION_SOURCE_LEN + // resolution_source
+        32 + // metadata_hash
+        1; // bump
+}
+
+/// create_proposal metadata argument (same layout as CoreMarkets MarketMetadataArgs)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MarketMetadataArgs {
+    pub category: MarketCategory,
+    pub tags: Vec<String>,
+    pub resolution_source: String,
+    pub metadata_hash: [u8; 32],
+}
+
 /// Vote record per voter per proposal
 #[account]
 pub struct ProposalVoteRecord {
@@ -359,6 +473,19 @@ pub enum BondTier {
     Tier3,  // 10 SOL → 2.0% creator fee
 }
 
+/// Market category (same order as CoreMarkets MarketCategory)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MarketCategory {
+    Politics,
+    Sports,
+    Crypto,
+    Technology,
+    Entertainment,
+    Science,
+    Economics,
+    Other,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum VoteChoice {
     Yes,
@@ -381,6 +508,15 @@ pub struct CreateProposal<'info> {
     )]
     pub proposal: Account<'info, Proposal>,
 
+    #[account(
+        init,
+        payer = creator,
+        space = ProposalMetadata::LEN,
+        seeds = [b"proposal-metadata", proposal_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal_metadata: Account<'info, ProposalMetadata>,
+
     /// Global parameters from ParameterStorage
     #[account(
         seeds = [b"global-parameters"],
@@ -389,6 +525,14 @@ pub struct CreateProposal<'info> {
     )]
     pub global_parameters: Account<'info, GlobalParameters>,
 
+    /// CHECK: Feature toggles from ParameterStorage - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
     #[account(mut)]
     pub creator: Signer<'info>,
 
@@ -420,10 +564,21 @@ pub struct VoteOnProposal<'info> {
     )]
     pub vote_record: Account<'info, ProposalVoteRecord>,
 
+    /// CHECK: Feature toggles from ParameterStorage - validated via seeds and owner
+    #[account(
+        seeds = [b"global-toggles"],
+        bump,
+        seeds::program = parameter_storage_program.key()
+    )]
+    pub global_toggles: AccountInfo<'info>,
+
     #[account(mut)]
     pub voter: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: ParameterStorage program ID
+    pub parameter_storage_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -451,10 +606,48 @@ pub struct RejectProposal<'info> {
     pub creator: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CloseProposalVoteRecord<'info> {
+    #[account(
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [
+            b"proposal-vote",
+            proposal.proposal_id.to_le_bytes().as_ref(),
+            voter.key().as_ref()
+        ],
+        bump = vote_record.bump
+    )]
+    pub vote_record: Account<'info, ProposalVoteRecord>,
+
+    /// CHECK: Voter who paid the rent - bound by the vote_record seeds
+    #[account(mut)]
+    pub voter: UncheckedAccount<'info>,
+}
+
 // ==============================================================================
 // External Account Structures (from ParameterStorage)
 // ==============================================================================
 
+#[account]
+pub struct GlobalFeatureToggles {
+    pub authority: Pubkey,
+    pub market_creation_enabled: bool,
+    pub betting_enabled: bool,
+    pub resolution_enabled: bool,
+    pub proposals_enabled: bool,
+    pub emergency_pause: bool,
+    pub last_updated: i64,
+    pub version: u32,
+    pub bump: u8,
+}
+
 #[account]
 pub struct GlobalParameters {
     pub authority: Pubkey,
@@ -491,6 +684,8 @@ pub struct ProposalCreatedEvent {
     pub bond_tier: BondTier,
     pub proposal_tax: u64,
     pub end_date: i64,
+    pub category: MarketCategory,
+    pub metadata_hash: [u8; 32], // Verifies the off-chain metadata JSON
     pub timestamp: i64,
 }
 
@@ -556,4 +751,22 @@ pub enum ProposalError {
 
     #[msg("Proposal not rejected: has ≥60% YES votes")]
     ProposalNotRejected,
+
+    #[msg("Invalid feature toggles account: must be the ParameterStorage global-toggles PDA")]
+    InvalidFeatureToggles,
+
+    #[msg("Protocol is under emergency pause")]
+    EmergencyPaused,
+
+    #[msg("Proposals are disabled")]
+    ProposalsDisabled,
+
+    #[msg("Proposal has not been approved or rejected yet")]
+    ProposalNotProcessed,
+
+    #[msg("Invalid tags: at most 5, each 1-32 characters")]
+    InvalidTags,
+
+    #[msg("Resolution source URI exceeds 200 characters")]
+    InvalidResolutionSource,
 }
@@ -0,0 +1,128 @@
+#!/usr/bin/env ts-node
+/**
+ * BMAD-Zmart Market Series Crank
+ *
+ * Spawns the next market of a recurring MarketSeries once the previous one
+ * has ended. Anyone can run it; the series PDA reimburses the rent.
+ *
+ * Usage:
+ *   ANCHOR_PROVIDER_URL=https://api.devnet.solana.com \
+ *   ANCHOR_WALLET=~/.config/solana/id.json \
+ *   npx ts-node scripts/spawn-series-market.ts <series_id>
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { CoreMarkets } from "../target/types/core_markets";
+
+const PARAMETER_STORAGE_PROGRAM_ID = new anchor.web3.PublicKey(
+  "J63ypBPAjWEMrwyFxWTP6vG8tGF58gH8w9G6yjDFqumD"
+);
+const SERIES_MARKET_ID_FLAG = new anchor.BN(1).shln(63);
+
+// Mirrors MarketSeries::market_id in core_markets
+function seriesMarketId(seriesId: number, index: number): anchor.BN {
+  return SERIES_MARKET_ID_FLAG.or(new anchor.BN(seriesId).shln(32)).or(new anchor.BN(index));
+}
+
+async function main() {
+  const seriesId = Number(process.argv[2]);
+  if (!Number.isInteger(seriesId) || seriesId < 0) {
+    console.error("Usage: npx ts-node scripts/spawn-series-market.ts <series_id>");
+    process.exit(1);
+  }
+
+  console.log(`🔁 Spawning next market for series ${seriesId}\n`);
+
+  // Setup provider
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.CoreMarkets as Program<CoreMarkets>;
+
+  const seriesIdBytes = Buffer.alloc(4);
+  seriesIdBytes.writeUInt32LE(seriesId);
+  const [seriesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("market-series"), seriesIdBytes],
+    program.programId
+  );
+
+  const series = await program.account.marketSeries.fetch(seriesPda);
+  const now = Math.floor(Date.now() / 1000);
+
+  console.log("📊 Series:");
+  console.log(`   Title pattern: ${series.template.titlePattern}`);
+  console.log(`   Markets created: ${series.marketsCreated}`);
+  console.log(`   Markets settled: ${series.marketsSettled}`);
+  console.log(`   Total volume: ${series.totalVolume.toString()} lamports`);
+  console.log(`   Active: ${series.active}\n`);
+
+  if (!series.active) {
+    console.log("⏸️  Series is paused, nothing to spawn");
+    return;
+  }
+  if (series.marketsCreated > 0 && now < series.lastEndDate.toNumber()) {
+    console.log(
+      `⏳ Previous market ends at ${new Date(series.lastEndDate.toNumber() * 1000).toISOString()}`
+    );
+    return;
+  }
+
+  const marketId = seriesMarketId(seriesId, series.marketsCreated);
+  const [marketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+  const [marketMetadataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("market-metadata"), marketPda.toBuffer()],
+    program.programId
+  );
+  const [globalParametersPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("global-parameters")],
+    PARAMETER_STORAGE_PROGRAM_ID
+  );
+  const [globalTogglesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("global-toggles")],
+    PARAMETER_STORAGE_PROGRAM_ID
+  );
+
+  try {
+    const tx = await program.methods
+      .spawnNextMarket()
+      .accounts({
+        series: seriesPda,
+        market: marketPda,
+        marketMetadata: marketMetadataPda,
+        cranker: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        parameterStorageProgram: PARAMETER_STORAGE_PROGRAM_ID,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx, "confirmed");
+
+    const market = await program.account.market.fetch(marketPda);
+    console.log("✅ Market spawned!");
+    console.log(`   TX: ${tx}`);
+    console.log(`   Market ID: ${marketId.toString()}`);
+    console.log(`   PDA: ${marketPda.toBase58()}`);
+    console.log(`   Title: ${market.title}`);
+    console.log(`   End Date: ${new Date(market.endDate.toNumber() * 1000).toISOString()}`);
+  } catch (error: any) {
+    console.error("\n❌ Spawn failed:", error.message);
+
+    if (error.logs) {
+      console.error("\n📜 Program logs:");
+      error.logs.forEach((log: string) => console.error(`   ${log}`));
+    }
+
+    process.exit(1);
+  }
+}
+
+main().catch((error) => {
+  console.error("Fatal error:", error);
+  process.exit(1);
+});
@@ -23,18 +23,18 @@ export interface MerkleTreeResult {
 }
 
 // ============================================================================
-// Merkle Root Generation (Simplified for MVP)
+// Merkle Root Generation
 // ============================================================================
 
 /**
  * Generate Merkle root from votes
  *
- * Simplified implementation for MVP:
+ * Matches the on-chain verifier (core_markets::verify_merkle_proof, used by
+ * market_resolution::VoteResult::verify_inclusion):
  * - Deterministic sorting (timestamp, then wallet)
- * - keccak256 hash of each vote
- * - Simple concatenation and hash (not full tree for now)
- *
- * For production: Upgrade to full Merkle tree with proof generation
+ * - Leaf = keccak256 of each vote (32 bytes)
+ * - Parent = keccak256(min(left, right) || max(left, right)) over raw bytes
+ * - An unpaired node at the end of a level moves up unchanged
  *
  * @param votes Array of votes from PostgreSQL
  * @returns Merkle root as hex string (32 bytes)
@@ -56,11 +56,11 @@ export function generateMerkleRoot(votes: Vote[]): MerkleTreeResult {
   // Step 2: Generate leaf hashes
   const leaves = sortedVotes.map(vote => hashVote(vote));
 
-  // Step 3: Build Merkle tree (simplified: hash all leaves together)
-  const root = buildSimpleMerkleRoot(leaves);
+  // Step 3: Build sorted-pair Merkle tree
+  const levels = buildMerkleLevels(leaves);
 
   return {
-    root,
+    root: levels[levels.length - 1][0],
     leafCount: leaves.length,
     leaves,
   };
@@ -103,7 +103,7 @@ function sortVotesDeterministically(votes: Vote[]): Vote[] {
  * @param vote Vote to hash
  * @returns Hex-encoded hash (32 bytes)
  */
-function hashVote(vote: Vote): string {
+export function hashVote(vote: Vote): string {
   // Concatenate vote data with | separator for clarity
   const voteData = [
     vote.voter_wallet,
@@ -117,96 +117,106 @@ function hashVote(vote: Vote): string {
 }
 
 /**
- * Build Merkle root from leaf hashes
- *
- * Simplified implementation for MVP:
- * - Concatenate all leaf hashes
- * - Hash the concatenation
+ * Hash two nodes in sorted order
  *
- * For production: Implement full binary Merkle tree
+ * Lowercase hex strings of equal length compare like their bytes, so the
+ * smaller string is the smaller 32-byte node.
  *
- * @param leaves Array of hex-encoded leaf hashes
- * @returns Merkle root as hex string
+ * @returns keccak256(min || max) as hex
  */
-function buildSimpleMerkleRoot(leaves: string[]): string {
-  if (leaves.length === 0) {
-    return keccak256('');
-  }
-
-  if (leaves.length === 1) {
-    return leaves[0];
-  }
-
-  // Concatenate all leaves and hash
-  const concatenated = leaves.join('');
-  return keccak256(concatenated);
+function hashPair(a: string, b: string): string {
+  const [first, second] = a <= b ? [a, b] : [b, a];
+  return keccak256(hexToBytes(first + second));
 }
 
-// ============================================================================
-// Binary Merkle Tree (Future Enhancement - Commented Out)
-// ============================================================================
-
 /**
- * Build full binary Merkle tree with proof generation
+ * Build every level of the tree, leaves first and root last
  *
- * Commented out for MVP - use simple concatenation instead.
- * Uncomment for production to enable trustless dispute resolution.
+ * @param leaves Hex-encoded leaf hashes (at least one)
  */
-/*
-function buildFullMerkleTree(leaves: string[]): string {
-  if (leaves.length === 0) return keccak256('');
-  if (leaves.length === 1) return leaves[0];
+function buildMerkleLevels(leaves: string[]): string[][] {
+  const levels = [leaves];
 
-  // Build tree level by level
-  let currentLevel = leaves;
-
-  while (currentLevel.length > 1) {
+  while (levels[levels.length - 1].length > 1) {
+    const currentLevel = levels[levels.length - 1];
     const nextLevel: string[] = [];
 
     for (let i = 0; i < currentLevel.length; i += 2) {
-      const left = currentLevel[i];
-      const right = currentLevel[i + 1] || left; // Duplicate if odd number
-
-      // Hash pair
-      const combined = left + right;
-      const parentHash = keccak256(combined);
-      nextLevel.push(parentHash);
+      // Unpaired last node moves up as is
+      nextLevel.push(
+        i + 1 < currentLevel.length
+          ? hashPair(currentLevel[i], currentLevel[i + 1])
+          : currentLevel[i]
+      );
     }
 
-    currentLevel = nextLevel;
+    levels.push(nextLevel);
   }
 
-  return currentLevel[0];
+  return levels;
+}
+
+function hexToBytes(hex: string): Uint8Array {
+  const bytes = new Uint8Array(hex.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(hex.slice(i * 2, i * 2 + 2), 16);
+  }
+  return bytes;
 }
-*/
 
 // ============================================================================
-// Merkle Proof Generation (Future Enhancement - Commented Out)
+// Merkle Proof Generation
 // ============================================================================
 
-/**
- * Generate Merkle proof for a specific vote
- *
- * Enables trustless verification that a vote was included in result.
- * Commented out for MVP - implement for production dispute resolution.
- */
-/*
 export interface MerkleProof {
   leaf: string; // Hash of the vote
-  siblings: string[]; // Sibling hashes up the tree
+  siblings: string[]; // Sibling hashes up the tree (no left/right flags needed)
   root: string; // Merkle root
 }
 
-export function generateMerkleProof(votes: Vote[], voteIndex: number): MerkleProof {
-  // Implementation for production
-  throw new Error('Merkle proof generation not implemented yet');
+/**
+ * Generate Merkle proof for a specific vote
+ *
+ * Enables trustless verification that a vote was included in the result:
+ * pass the leaf and siblings to VoteResult::verify_inclusion on-chain.
+ *
+ * @param votes All votes of the market (any order)
+ * @param vote The vote to prove
+ */
+export function generateMerkleProof(votes: Vote[], vote: Vote): MerkleProof {
+  const leaves = sortVotesDeterministically(votes).map(v => hashVote(v));
+  const leaf = hashVote(vote);
+  let index = leaves.indexOf(leaf);
+  if (index === -1) {
+    throw new Error('Vote is not part of the vote set');
+  }
+
+  const levels = buildMerkleLevels(leaves);
+  const siblings: string[] = [];
+
+  for (const level of levels.slice(0, -1)) {
+    const siblingIndex = index % 2 === 0 ? index + 1 : index - 1;
+    // No sibling: the node moved up unchanged
+    if (siblingIndex < level.length) {
+      siblings.push(level[siblingIndex]);
+    }
+    index = Math.floor(index / 2);
+  }
+
+  return {
+    leaf,
+    siblings,
+    root: levels[levels.length - 1][0],
+  };
 }
 
+/**
+ * Verify a Merkle proof off-chain (same rules as the on-chain verifier)
+ */
 export function verifyMerkleProof(proof: MerkleProof): boolean {
-  // Implementation for production
-  throw new Error('Merkle proof verification not implemented yet');
+  const computed = proof.siblings.reduce((node, sibling) => hashPair(node, sibling), proof.leaf);
+  return computed === proof.root;
 }
-*/
 
 // ============================================================================
 // Validation Functions
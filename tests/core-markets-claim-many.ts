@@ -0,0 +1,482 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { CoreMarkets } from "../target/types/core_markets";
+import { ParameterStorage } from "../target/types/parameter_storage";
+import { BondManager } from "../target/types/bond_manager";
+import { MarketResolution } from "../target/types/market_resolution";
+import { expect } from "chai";
+import {
+  PublicKey,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  ComputeBudgetProgram,
+  AccountMeta,
+} from "@solana/web3.js";
+
+/**
+ * CoreMarkets claim_many - Batch Claim Tests
+ *
+ * Coverage:
+ * - Refunds across many cancelled markets in one transaction
+ * - Payouts across many resolved markets in one transaction
+ * - Already-claimed and lost bets are skipped without failing the batch
+ * - Bets on markets still open fail the batch
+ * - Compute units consumed per batch size
+ *
+ * Legacy transactions fit at most 10 (market, user_bet, user_position)
+ * triples; larger batches (up to MAX_CLAIM_BATCH = 20) need an address
+ * lookup table.
+ *
+ * Markets must outlive GlobalParameters.min_duration_seconds before they can
+ * be cancelled, and must still be open for betting after the
+ * betting_cutoff_seconds buffer, so these tests are skipped unless the
+ * validator's parameters allow short markets (<= MAX_TEST_DURATION_SECONDS).
+ * Resolved markets are settled through MarketResolution's admin override,
+ * which signs the resolve_market CPI with the resolver PDA.
+ */
+
+describe("CoreMarkets claim_many", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.CoreMarkets as Program<CoreMarkets>;
+  const parameterProgram = anchor.workspace.ParameterStorage as Program<ParameterStorage>;
+  const bondProgram = anchor.workspace.BondManager as Program<BondManager>;
+  const resolutionProgram = anchor.workspace.MarketResolution as Program<MarketResolution>;
+
+  const BATCH_SIZES = [1, 5, 10];
+  const MAX_CLAIM_BATCH = 20; // Program limit (MAX_CLAIM_BATCH)
+  const BET_AMOUNT = new BN(0.05 * LAMPORTS_PER_SOL);
+  const MAX_TEST_DURATION_SECONDS = 60;
+  let marketDurationSeconds = 5;
+
+  let globalParametersPda: PublicKey;
+  let globalTogglesPda: PublicKey;
+  let treasuryPda: PublicKey;
+  let resolverAuthorityPda: PublicKey;
+  let creator: Keypair;
+  let bettor: Keypair;
+  let opponent: Keypair;
+  let nextMarketId = new BN(Date.now()).mul(new BN(1000));
+
+  interface BetAccounts {
+    marketPda: PublicKey;
+    userBetPda: PublicKey;
+    userPositionPda: PublicKey;
+  }
+
+  type MarketBet = BetAccounts & { marketId: BN };
+
+  async function createAndFundAccount(lamports: number = 100 * LAMPORTS_PER_SOL): Promise<Keypair> {
+    const keypair = Keypair.generate();
+    const airdropSignature = await provider.connection.requestAirdrop(
+      keypair.publicKey,
+      lamports
+    );
+    await provider.connection.confirmTransaction(airdropSignature);
+    return keypair;
+  }
+
+  function sleep(ms: number): Promise<void> {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+  }
+
+  function betAccounts(marketPda: PublicKey, owner: PublicKey): BetAccounts {
+    const [userPositionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user-position"), marketPda.toBuffer(), owner.toBuffer()],
+      program.programId
+    );
+    const [userBetPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user-bet"),
+        marketPda.toBuffer(),
+        owner.toBuffer(),
+        new BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    return { marketPda, userBetPda, userPositionPda };
+  }
+
+  // Helper: Place a first bet of BET_AMOUNT on `marketPda`
+  async function placeBet(
+    marketPda: PublicKey,
+    bondEscrowPda: PublicKey,
+    owner: Keypair,
+    side: object
+  ): Promise<BetAccounts> {
+    const accounts = betAccounts(marketPda, owner.publicKey);
+    await program.methods
+      .placeBet(side as any, BET_AMOUNT, 0, 10000, [])
+      .accounts({
+        market: marketPda,
+        userPosition: accounts.userPositionPda,
+        userBet: accounts.userBetPda,
+        globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        bondEscrow: bondEscrowPda,
+        bettor: owner.publicKey,
+        feePayer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        parameterStorageProgram: parameterProgram.programId,
+        bondManagerProgram: bondProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+    return accounts;
+  }
+
+  // Helper: Create a short-lived SOL market with a YES bet from `bettor`
+  // (and a NO bet from `opponent` if requested, so a resolution has winners)
+  async function createMarketWithBet(withOpponent: boolean = false): Promise<MarketBet> {
+    nextMarketId = nextMarketId.add(new BN(1));
+    const marketId = nextMarketId;
+    const marketIdBytes = marketId.toArrayLike(Buffer, "le", 8);
+
+    const [marketPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("market"), marketIdBytes],
+      program.programId
+    );
+    const [marketMetadataPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("market-metadata"), marketPda.toBuffer()],
+      program.programId
+    );
+    const [bondEscrowPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bond-escrow"), marketIdBytes],
+      bondProgram.programId
+    );
+
+    await bondProgram.methods
+      .depositBond(marketId, { tier1: {} })
+      .accounts({
+        bondEscrow: bondEscrowPda,
+        globalParameters: globalParametersPda,
+        creator: creator.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        parameterStorageProgram: parameterProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const endDate = Math.floor(Date.now() / 1000) + marketDurationSeconds;
+    await program.methods
+      .createMarket(
+        marketId,
+        `Batch claim market ${marketId}`,
+        "claim_many compute unit measurement",
+        new BN(endDate),
+        { binary: {} },
+        [],
+        { parimutuel: {} },
+        {
+          category: { other: {} },
+          tags: ["test"],
+          resolutionSource: "",
+          metadataHash: Array(32).fill(0),
+        },
+        null,
+        null
+      )
+      .accounts({
+        market: marketPda,
+        marketMetadata: marketMetadataPda,
+        creator: creator.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        parameterStorageProgram: parameterProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const bet = await placeBet(marketPda, bondEscrowPda, bettor, { yes: {} });
+    if (withOpponent) {
+      await placeBet(marketPda, bondEscrowPda, opponent, { no: {} });
+    }
+
+    return { marketId, ...bet };
+  }
+
+  // Helper: Cancel a market once its end date has passed (authority-only)
+  async function cancelMarket(marketPda: PublicKey): Promise<void> {
+    await program.methods
+      .cancelMarket()
+      .accounts({
+        market: marketPda,
+        globalParameters: globalParametersPda,
+        authority: provider.wallet.publicKey,
+        parameterStorageProgram: parameterProgram.programId,
+      })
+      .rpc();
+  }
+
+  // Helper: Resolve an ended market through MarketResolution's admin override
+  async function resolveMarket(bet: MarketBet, outcome: object): Promise<void> {
+    const [resolutionStatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("resolution-state"), bet.marketId.toArrayLike(Buffer, "le", 8)],
+      resolutionProgram.programId
+    );
+
+    await resolutionProgram.methods
+      .initializeResolution(bet.marketId)
+      .accounts({
+        resolutionState: resolutionStatePda,
+        globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        authority: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        parameterStorageProgram: parameterProgram.programId,
+      })
+      .rpc();
+
+    await resolutionProgram.methods
+      .adminOverrideResolution(outcome as any)
+      .accounts({
+        resolutionState: resolutionStatePda,
+        globalParameters: globalParametersPda,
+        coreMarkets: {
+          market: bet.marketPda,
+          globalParameters: globalParametersPda,
+          globalToggles: globalTogglesPda,
+          platformWallet: provider.wallet.publicKey,
+          creatorWallet: creator.publicKey,
+          treasury: treasuryPda,
+          resolverAuthority: resolverAuthorityPda,
+          coreMarketsProgram: program.programId,
+          parameterStorageProgram: parameterProgram.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          collateralMint: null,
+          marketVault: null,
+          platformTokenAccount: null,
+          creatorTokenAccount: null,
+          treasuryTokenAccount: null,
+          tokenProgram: null,
+          series: null,
+        },
+        admin: provider.wallet.publicKey,
+        parameterStorageProgram: parameterProgram.programId,
+      } as any)
+      .rpc();
+  }
+
+  function claimManyAccounts(bets: BetAccounts[]): AccountMeta[] {
+    return bets.flatMap((bet) => [
+      { pubkey: bet.marketPda, isSigner: false, isWritable: true },
+      { pubkey: bet.userBetPda, isSigner: false, isWritable: true },
+      { pubkey: bet.userPositionPda, isSigner: false, isWritable: true },
+    ]);
+  }
+
+  // Helper: Send claim_many and return compute units consumed
+  async function claimMany(bets: BetAccounts[]): Promise<number> {
+    const signature = await program.methods
+      .claimMany()
+      .accounts({ bettor: bettor.publicKey })
+      .remainingAccounts(claimManyAccounts(bets))
+      .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+      .signers([bettor])
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return tx?.meta?.computeUnitsConsumed ?? 0;
+  }
+
+  async function createCancelledBets(count: number): Promise<BetAccounts[]> {
+    const bets: BetAccounts[] = [];
+    for (let i = 0; i < count; i++) {
+      bets.push(await createMarketWithBet());
+    }
+    await sleep((marketDurationSeconds + 2) * 1000);
+    for (const bet of bets) {
+      await cancelMarket(bet.marketPda);
+    }
+    return bets;
+  }
+
+  async function createResolvedBets(count: number, outcome: object): Promise<MarketBet[]> {
+    const bets: MarketBet[] = [];
+    for (let i = 0; i < count; i++) {
+      bets.push(await createMarketWithBet(true));
+    }
+    await sleep((marketDurationSeconds + 2) * 1000);
+    for (const bet of bets) {
+      await resolveMarket(bet, outcome);
+    }
+    return bets;
+  }
+
+  before(async () => {
+    console.log("\n📦 Setting up claim_many test environment...\n");
+
+    [globalParametersPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global-parameters")],
+      parameterProgram.programId
+    );
+    [globalTogglesPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global-toggles")],
+      parameterProgram.programId
+    );
+    [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    [resolverAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("resolver-authority")],
+      resolutionProgram.programId
+    );
+
+    const globalParams = await parameterProgram.account.globalParameters.fetch(globalParametersPda);
+    marketDurationSeconds = Math.max(
+      marketDurationSeconds,
+      globalParams.minDurationSeconds.toNumber() + 1,
+      globalParams.bettingCutoffSeconds.toNumber() + 5
+    );
+
+    creator = await createAndFundAccount();
+    bettor = await createAndFundAccount();
+    opponent = await createAndFundAccount();
+
+    // Resolution pays treasury fees into the treasury PDA
+    if (!(await provider.connection.getAccountInfo(treasuryPda))) {
+      await program.methods
+        .initializeTreasury()
+        .accounts({
+          treasury: treasuryPda,
+          globalParameters: globalParametersPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          parameterStorageProgram: parameterProgram.programId,
+        })
+        .rpc();
+    }
+
+    console.log("✅ Test accounts created and funded\n");
+  });
+
+  describe("Batch refunds", () => {
+    const computeUnits: Record<number, number> = {};
+
+    for (const batchSize of BATCH_SIZES) {
+      it(`Should refund ${batchSize} cancelled market(s) in one transaction`, async function () {
+        if (marketDurationSeconds > MAX_TEST_DURATION_SECONDS) this.skip();
+
+        const bets = await createCancelledBets(batchSize);
+
+        const balanceBefore = await provider.connection.getBalance(bettor.publicKey);
+        computeUnits[batchSize] = await claimMany(bets);
+        const balanceAfter = await provider.connection.getBalance(bettor.publicKey);
+
+        for (const bet of bets) {
+          const userBet = await program.account.userBet.fetch(bet.userBetPda);
+          const position = await program.account.userPosition.fetch(bet.userPositionPda);
+          expect(userBet.claimed).to.equal(true);
+          expect(position.settledCount.toNumber()).to.equal(1);
+          expect(position.totalClaimed.toString()).to.equal(BET_AMOUNT.toString());
+        }
+
+        // Refunds cover the full bet amounts (less the transaction fee)
+        const refunded = balanceAfter - balanceBefore;
+        expect(refunded).to.be.greaterThan(BET_AMOUNT.toNumber() * batchSize - 0.01 * LAMPORTS_PER_SOL);
+        expect(computeUnits[batchSize]).to.be.lessThan(1_400_000);
+      });
+    }
+
+    it("Should skip already-claimed bets without failing the batch", async function () {
+      if (marketDurationSeconds > MAX_TEST_DURATION_SECONDS) this.skip();
+
+      const bets = await createCancelledBets(2);
+      await claimMany(bets.slice(0, 1));
+
+      const balanceBefore = await provider.connection.getBalance(bettor.publicKey);
+      await claimMany(bets);
+      const balanceAfter = await provider.connection.getBalance(bettor.publicKey);
+
+      // Only the second bet pays out
+      const refunded = balanceAfter - balanceBefore;
+      expect(refunded).to.be.lessThan(BET_AMOUNT.toNumber() * 2 - 0.01 * LAMPORTS_PER_SOL);
+      const second = await program.account.userBet.fetch(bets[1].userBetPda);
+      expect(second.claimed).to.equal(true);
+    });
+
+    it("Should fail the batch when a market is still open", async () => {
+      // Runs whatever the parameters: the market never needs to end
+      const bet = await createMarketWithBet();
+
+      try {
+        await claimMany([bet]);
+        expect.fail("Should have thrown MarketNotResolved error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MarketNotResolved");
+      }
+
+      const userBet = await program.account.userBet.fetch(bet.userBetPda);
+      expect(userBet.claimed).to.equal(false);
+    });
+
+    it("Should keep compute units per bet flat as the batch grows", function () {
+      const measured = BATCH_SIZES.filter((batchSize) => computeUnits[batchSize] !== undefined);
+      if (measured.length < 2) this.skip();
+
+      const perBet = (batchSize: number) => computeUnits[batchSize] / batchSize;
+      const single = perBet(measured[0]);
+      for (const batchSize of measured.slice(1)) {
+        // Larger batches only add per-bet work (the fixed overhead is shared)
+        expect(computeUnits[batchSize]).to.be.greaterThan(computeUnits[measured[0]]);
+        expect(perBet(batchSize)).to.be.at.most(single * 1.1);
+      }
+
+      // The largest batch the program accepts still fits the compute budget
+      const largest = measured[measured.length - 1];
+      expect(perBet(largest) * MAX_CLAIM_BATCH).to.be.lessThan(1_400_000);
+    });
+  });
+
+  describe("Batch payouts", () => {
+    it("Should pay out winning bets on 5 resolved markets in one transaction", async function () {
+      if (marketDurationSeconds > MAX_TEST_DURATION_SECONDS) this.skip();
+
+      const bets = await createResolvedBets(5, { yes: {} });
+
+      const balanceBefore = await provider.connection.getBalance(bettor.publicKey);
+      await claimMany(bets);
+      const balanceAfter = await provider.connection.getBalance(bettor.publicKey);
+
+      // Each winner takes its stake plus the opponent's losing stake (less fees)
+      for (const bet of bets) {
+        const userBet = await program.account.userBet.fetch(bet.userBetPda);
+        const position = await program.account.userPosition.fetch(bet.userPositionPda);
+        expect(userBet.claimed).to.equal(true);
+        expect(position.settledCount.toNumber()).to.equal(1);
+        expect(position.totalClaimed.gt(BET_AMOUNT)).to.equal(true);
+      }
+      expect(balanceAfter - balanceBefore).to.be.greaterThan(BET_AMOUNT.toNumber() * bets.length);
+    });
+
+    it("Should skip lost bets without failing the batch", async function () {
+      if (marketDurationSeconds > MAX_TEST_DURATION_SECONDS) this.skip();
+
+      const won = await createMarketWithBet(true);
+      const lost = await createMarketWithBet(true);
+      await sleep((marketDurationSeconds + 2) * 1000);
+      await resolveMarket(won, { yes: {} });
+      await resolveMarket(lost, { no: {} });
+
+      const balanceBefore = await provider.connection.getBalance(bettor.publicKey);
+      await claimMany([lost, won]);
+      const balanceAfter = await provider.connection.getBalance(bettor.publicKey);
+
+      const lostBet = await program.account.userBet.fetch(lost.userBetPda);
+      const wonBet = await program.account.userBet.fetch(won.userBetPda);
+      expect(lostBet.claimed).to.equal(false);
+      expect(wonBet.claimed).to.equal(true);
+
+      // Only the winning bet pays out
+      const wonPosition = await program.account.userPosition.fetch(won.userPositionPda);
+      expect(balanceAfter - balanceBefore).to.be.lessThan(wonPosition.totalClaimed.toNumber());
+      expect(balanceAfter - balanceBefore).to.be.greaterThan(BET_AMOUNT.toNumber());
+    });
+  });
+});
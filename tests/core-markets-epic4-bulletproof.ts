@@ -116,22 +116,34 @@ describe("🛡️ EPIC 4: Resolution & Payout - BULLETPROOF TESTS", () => {
     betSide: any,
     amount: BN
   ): Promise<PublicKey> {
-    const market = await coreMarketsProgram.account.market.fetch(marketPda);
+    const [userPositionPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user-position"),
+        marketPda.toBuffer(),
+        bettor.publicKey.toBuffer(),
+      ],
+      coreMarketsProgram.programId
+    );
+    const position = await coreMarketsProgram.account.userPosition.fetchNullable(
+      userPositionPda
+    );
+    const betIndex = position ? position.betCount : new BN(0);
 
     const [userBetPda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from("user-bet"),
         marketPda.toBuffer(),
         bettor.publicKey.toBuffer(),
-        market.totalBets.toArrayLike(Buffer, "le", 8),
+        betIndex.toArrayLike(Buffer, "le", 8),
       ],
       coreMarketsProgram.programId
     );
 
     await coreMarketsProgram.methods
-      .placeBet(betSide, amount)
+      .placeBet(betSide, amount, 0, 10000)
       .accounts({
         market: marketPda,
+        userPosition: userPositionPda,
         userBet: userBetPda,
         globalParameters: globalParametersPda,
         bettor: bettor.publicKey,
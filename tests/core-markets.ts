@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { CoreMarkets } from "../target/types/core_markets";
 import { ParameterStorage } from "../target/types/parameter_storage";
+import { BondManager } from "../target/types/bond_manager";
 import { expect } from "chai";
 import { PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
 
@@ -26,12 +27,15 @@ describe("CoreMarkets Program Tests", () => {
 
   const program = anchor.workspace.CoreMarkets as Program<CoreMarkets>;
   const parameterProgram = anchor.workspace.ParameterStorage as Program<ParameterStorage>;
+  const bondProgram = anchor.workspace.BondManager as Program<BondManager>;
 
   const authority = provider.wallet as anchor.Wallet;
 
   // PDAs
   let globalParametersPda: PublicKey;
+  let globalTogglesPda: PublicKey;
   let platformWallet: PublicKey;
+  let lastMarketId = new BN(Date.now()).mul(new BN(1000));
 
   // Test accounts
   let creator: Keypair;
@@ -54,10 +58,63 @@ describe("CoreMarkets Program Tests", () => {
     return Math.floor(Date.now() / 1000);
   }
 
-  // Helper: Get next market ID
+  // Helper: Get next market ID (callers choose ids; unique per test run)
   async function getNextMarketId(): Promise<BN> {
-    const globalParams = await parameterProgram.account.globalParameters.fetch(globalParametersPda);
-    return globalParams.totalMarkets.add(new BN(1));
+    lastMarketId = lastMarketId.add(new BN(1));
+    return lastMarketId;
+  }
+
+  function bondEscrowPda(marketId: BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("bond-escrow"), marketId.toArrayLike(Buffer, "le", 8)],
+      bondProgram.programId
+    )[0];
+  }
+
+  // Helper: create_market call for a binary parimutuel SOL market
+  function createMarketMethod(
+    creator: Keypair,
+    marketId: BN,
+    title: string,
+    description: string,
+    endDate: number
+  ) {
+    const [marketPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [marketMetadataPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("market-metadata"), marketPda.toBuffer()],
+      program.programId
+    );
+
+    return program.methods
+      .createMarket(
+        marketId,
+        title,
+        description,
+        new BN(endDate),
+        { binary: {} },
+        [],
+        { parimutuel: {} },
+        {
+          category: { other: {} },
+          tags: ["test"],
+          resolutionSource: "",
+          metadataHash: Array(32).fill(0),
+        },
+        null,
+        null
+      )
+      .accounts({
+        market: marketPda,
+        marketMetadata: marketMetadataPda,
+        creator: creator.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        parameterStorageProgram: parameterProgram.programId,
+      });
   }
 
   // Helper: Create test market
@@ -75,22 +132,29 @@ describe("CoreMarkets Program Tests", () => {
 
     const endDate = getCurrentTimestamp() + endDateOffset;
 
-    await program.methods
-      .createMarket(
-        options.title || `Test Market ${marketId}`,
-        options.description || "Test market description",
-        new BN(endDate)
-      )
+    // Bets read the creator's bond tier for the creator fee
+    await bondProgram.methods
+      .depositBond(marketId, { tier1: {} })
       .accounts({
-        market: marketPda,
-        creator: creator.publicKey,
+        bondEscrow: bondEscrowPda(marketId),
         globalParameters: globalParametersPda,
-        parameterStorageProgram: parameterProgram.programId,
+        creator: creator.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
+        parameterStorageProgram: parameterProgram.programId,
       })
       .signers([creator])
       .rpc();
 
+    await createMarketMethod(
+      creator,
+      marketId,
+      options.title || `Test Market ${marketId}`,
+      options.description || "Test market description",
+      endDate
+    )
+      .signers([creator])
+      .rpc();
+
     return { marketPda, marketId, endDate };
   }
 
@@ -101,26 +165,44 @@ describe("CoreMarkets Program Tests", () => {
     betSide: any,
     amount: BN
   ): Promise<PublicKey> {
-    const market = await program.account.market.fetch(marketPda);
+    const [userPositionPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user-position"),
+        marketPda.toBuffer(),
+        bettor.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const position = await program.account.userPosition.fetchNullable(
+      userPositionPda
+    );
+    const betIndex = position ? position.betCount : new BN(0);
 
     const [userBetPda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from("user-bet"),
         marketPda.toBuffer(),
         bettor.publicKey.toBuffer(),
-        market.totalBets.toArrayLike(Buffer, "le", 8),
+        betIndex.toArrayLike(Buffer, "le", 8),
       ],
       program.programId
     );
 
+    const market = await program.account.market.fetch(marketPda);
+
     await program.methods
-      .placeBet(betSide, amount)
+      .placeBet(betSide, amount, 0, 10000, [])
       .accounts({
         market: marketPda,
+        userPosition: userPositionPda,
         userBet: userBetPda,
         globalParameters: globalParametersPda,
+        globalToggles: globalTogglesPda,
+        bondEscrow: bondEscrowPda(market.marketId),
         bettor: bettor.publicKey,
+        feePayer: bettor.publicKey,
         parameterStorageProgram: parameterProgram.programId,
+        bondManagerProgram: bondProgram.programId,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([bettor])
@@ -137,6 +219,10 @@ describe("CoreMarkets Program Tests", () => {
       [Buffer.from("global-parameters")],
       parameterProgram.programId
     );
+    [globalTogglesPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global-toggles")],
+      parameterProgram.programId
+    );
 
     const globalParams = await parameterProgram.account.globalParameters.fetch(globalParametersPda);
     platformWallet = globalParams.authority;
@@ -164,15 +250,7 @@ describe("CoreMarkets Program Tests", () => {
         program.programId
       );
 
-      await program.methods
-        .createMarket(title, description, new BN(endDate))
-        .accounts({
-          market: marketPda,
-          creator: creator.publicKey,
-          globalParameters: globalParametersPda,
-          parameterStorageProgram: parameterProgram.programId,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
+      await createMarketMethod(creator, marketId, title, description, endDate)
         .signers([creator])
         .rpc();
 
@@ -191,21 +269,9 @@ describe("CoreMarkets Program Tests", () => {
 
     it("Should reject empty title", async () => {
       const marketId = await getNextMarketId();
-      const [marketPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
-        program.programId
-      );
 
       try {
-        await program.methods
-          .createMarket("", "Description", new BN(getCurrentTimestamp() + 3600))
-          .accounts({
-            market: marketPda,
-            creator: creator.publicKey,
-            globalParameters: globalParametersPda,
-            parameterStorageProgram: parameterProgram.programId,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
+        await createMarketMethod(creator, marketId, "", "Description", getCurrentTimestamp() + 3600)
           .signers([creator])
           .rpc();
 
@@ -217,21 +283,9 @@ describe("CoreMarkets Program Tests", () => {
 
     it("Should reject past end_date", async () => {
       const marketId = await getNextMarketId();
-      const [marketPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
-        program.programId
-      );
 
       try {
-        await program.methods
-          .createMarket("Test", "Description", new BN(getCurrentTimestamp() - 3600))
-          .accounts({
-            market: marketPda,
-            creator: creator.publicKey,
-            globalParameters: globalParametersPda,
-            parameterStorageProgram: parameterProgram.programId,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
+        await createMarketMethod(creator, marketId, "Test", "Description", getCurrentTimestamp() - 3600)
           .signers([creator])
           .rpc();
 